@@ -0,0 +1,684 @@
+use crate::components::*;
+use crate::data::*;
+use dioxus_signals::*;
+use uuid::Uuid;
+
+use std::{rc::Rc, ops::Deref};
+
+use dioxus::{
+    html::input_data::keyboard_types::{Key, Modifiers},
+    prelude::*,
+};
+
+use crate::{colours::Colour, pages::{chat::ChatPage, persona::PersonaPage, settings::SettingsPage, trends::TrendsPage, Page}};
+
+#[component]
+pub fn App(cx: Scope) -> Element {
+    AppState::load(cx);
+
+    cx.render(rsx! { Layout {} })
+}
+
+/// Everything below the loaded [`AppState`]
+pub fn Layout(cx: Scope) -> Element {
+    let app_state = AppState::use_app_context(cx);
+    let settings_css = app_state.settings.read().css();
+    let page = *app_state.page.read();
+    let shortcuts_open = use_signal(cx, || false);
+    let palette_open = use_signal(cx, || false);
+    use_context_provider(cx, SlashRegistry::builtin);
+
+    // Keeps the browser from acting on bound keys, reinstalled whenever they're rebound
+    let eval = use_eval(cx);
+    let install = eval.clone();
+    dioxus_signals::use_effect(cx, move || {
+        install(&app_state.settings.read().keymap.prevent_default_js()).unwrap();
+    });
+
+    let run = move |action: Action| match action {
+        Action::SelectPersona(n) => app_state.select_persona(n),
+        Action::NewChat => AppState::start_chat(cx),
+        Action::NextChat | Action::PreviousChat => {
+            let next = app_state.chats.read().cycle_chat(action == Action::PreviousChat);
+            if let Some(uuid) = next {
+                AppState::set_active_chat(cx, uuid);
+                app_state.page.set(Page::Chat);
+            }
+        }
+        Action::Search => {
+            eval(r#"document.getElementById("searchDialog").showModal();"#).unwrap();
+        }
+        Action::FilterChats => {
+            eval(r#"document.getElementById("chatFilter").focus();"#).unwrap();
+        }
+        Action::Rename => {
+            if app_state.active_chat.read().is_some() {
+                app_state.renaming.set(true);
+            }
+        }
+        Action::ShowShortcuts => {
+            let open = *shortcuts_open.read();
+            shortcuts_open.set(!open);
+        }
+        Action::CommandPalette => {
+            let open = *palette_open.read();
+            palette_open.set(!open);
+        }
+        Action::ExportChat => {
+            if let Some(chat) = *app_state.active_chat.read() {
+                let chat = ChatData::from(&chat);
+                let markdown = chat.to_markdown(&app_state.personas.read());
+                eval(&download_js(&format!("{}.md", chat.name), "text/markdown", &markdown)).unwrap();
+            }
+        }
+        Action::DeleteChat => AppState::delete_active_chat(cx),
+        Action::OpenSettings => app_state.page.set(Page::Settings),
+        Action::Send | Action::Newline | Action::NextPersona | Action::PreviousPersona => {}
+    };
+
+    cx.render(rsx! {
+        style { "{settings_css}" }
+        div {
+            class: "flex flex-1 font-sans w-full h-screen text-ink",
+            onkeydown: move |evt| {
+                if *shortcuts_open.read() && evt.key() == Key::Escape {
+                    shortcuts_open.set(false);
+                    return;
+                }
+                let action = app_state.settings.read().keymap.action(&evt.key(), evt.modifiers());
+                // The message input handles its own
+                if let Some(action) = action.filter(|action| !action.in_message_input()) {
+                    run(action);
+                }
+            },
+            SideBar {}
+            div {
+                class: "grid gap-y-2 h-full w-full pb-2 bg-surface items-center text-center",
+                style: "grid-template-rows: auto minmax(0, 1fr);",
+                h1 { class: "text-4xl font-bold mb-auto pb-2 w-full bg-panel", "Let Me Talk" }
+                // TODO Router for different pages
+                div { class: "mx-auto px-2 w-full h-full max-w-3xl", 
+                    match page {
+                        Page::Settings => rsx! { SettingsPage {} },
+                        Page::Trends => rsx! { TrendsPage {} },
+                        Page::Persona(uuid) => rsx! { PersonaPage { persona: uuid } },
+                        Page::Chat => match app_state.active_chat.read().deref() {
+                            Some(chat) => rsx! { ChatPage { chat: *chat } },
+                            None => rsx! { "" },
+                        },
+                    }
+                }
+            }
+            SearchDialog { id: "searchDialog" }
+            if *shortcuts_open.read() {
+                rsx! { ShortcutsOverlay { on_close: move |_| shortcuts_open.set(false) } }
+            }
+            if *palette_open.read() {
+                rsx! {
+                    CommandPalette {
+                        on_close: move |_| palette_open.set(false),
+                        on_action: move |action| run(action),
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Lets the browser drop onto elements marked `data-drop`
+const ALLOW_DROPS_JS: &str = r#"
+    if (!window.dropsAllowed) {
+        window.dropsAllowed = true;
+        document.addEventListener("dragover", (evt) => {
+            if (evt.target.closest("[data-drop]")) {
+                evt.preventDefault();
+            }
+        });
+    }
+"#;
+
+/// Shared by the parts of the sidebar
+#[derive(Clone, Copy)]
+struct SidebarState {
+    /// Just for mobile
+    open: Signal<bool>,
+    dragging: Signal<Option<Dragged>>,
+}
+
+impl SidebarState {
+    fn use_context(cx: &ScopeState) -> Self {
+        *use_context(cx).expect("sidebar parts must be inside the SideBar")
+    }
+
+    /// Files whatever is being dragged at `target`
+    fn drop_on(&self, chats: Signal<Chats>, target: DropTarget) {
+        if let Some(dragged) = *self.dragging.read() {
+            chats.write().refile(dragged, target);
+        }
+        self.dragging.set(None);
+    }
+}
+
+fn SideBar(cx: Scope) -> Element {
+    let chats = AppState::chats(cx);
+
+    // Just for mobile
+    let sidebar_open = use_signal(cx, || false);
+    let sidebar_style = use_signal(cx, || "hidden");
+    let open_sidebar_style = use_signal(cx, || "flex");
+    dioxus_signals::use_effect(cx, move || {
+        if *sidebar_open.read() {
+            sidebar_style.set("flex flex-col");
+            open_sidebar_style.set("hidden");
+        } else {
+            open_sidebar_style.set("flex");
+            sidebar_style.set("hidden");
+        }
+    });
+    let dragging = use_signal(cx, || None);
+    use_context_provider(cx, || SidebarState { open: sidebar_open, dragging });
+
+    // Drops are only allowed where the dragover is cancelled
+    let eval = use_eval(cx);
+    let install = eval.clone();
+    dioxus_signals::use_effect(cx, move || {
+        install(ALLOW_DROPS_JS).unwrap();
+    });
+
+    let picking_template = use_signal(cx, || false);
+    let templates = AppState::settings(cx).read().templates.clone();
+
+    let pinned = chats.read().pinned();
+    let folders = chats.read().library.subfolders(None);
+    // Built once here, each folder takes its own chats from it
+    let by_folder = Rc::new(chats.read().by_folder());
+    let unfiled = by_folder.get(&None).cloned().unwrap_or_default();
+    cx.render(rsx! {
+        button {
+            class: "bg-accent text-on-accent {open_sidebar_style} absolute md:hidden",
+            "style": "height: 40px;",
+            onclick: move |_| {
+                sidebar_open.set(true);
+            },
+            "OPEN"
+        }
+        div {
+            class: "{sidebar_style} md:flex md:flex-col bg-sidebar",
+            "style": "width: 260px;",
+            div { class: "flex",
+                button {
+                    class: "bg-accent text-on-accent",
+                    title: "Start a chat, from a template if there are any",
+                    onclick: move |_| {
+                        if AppState::settings(cx).read().templates.is_empty() {
+                            AppState::start_chat(cx);
+                        } else {
+                            let picking = *picking_template.read();
+                            picking_template.set(!picking);
+                        }
+                    },
+                    "New Chat"
+                }
+                button {
+                    class: "bg-raised",
+                    onclick: move |_| {
+                        eval(r#"document.getElementById("syncDialog").showModal();"#).unwrap();
+                    },
+                    "Sync"
+                }
+                button {
+                    class: "bg-raised",
+                    onclick: move |_| {
+                        AppState::page(cx).set(Page::Settings);
+                        sidebar_open.set(false);
+                    },
+                    "Settings"
+                }
+                button {
+                    class: "bg-raised",
+                    title: "How the check-ins have changed",
+                    onclick: move |_| {
+                        AppState::page(cx).set(Page::Trends);
+                        sidebar_open.set(false);
+                    },
+                    "Trends"
+                }
+            }
+            if *picking_template.read() {
+                rsx! {
+                    div { class: "flex flex-col gap-1 text-sm bg-raised p-1",
+                        button {
+                            class: "text-left",
+                            onclick: move |_| {
+                                picking_template.set(false);
+                                AppState::start_chat(cx);
+                            },
+                            "Blank chat"
+                        }
+                        templates.into_iter().map(|template| {
+                            let (uuid, name) = (template.uuid, template.name.clone());
+                            rsx! {
+                                button {
+                                    key: "{uuid}",
+                                    class: "text-left",
+                                    onclick: move |_| {
+                                        picking_template.set(false);
+                                        AppState::start_chat_from(cx, &template);
+                                    },
+                                    "{name}"
+                                }
+                            }
+                        })
+                    }
+                }
+            }
+            SyncDialog { id: "syncDialog" }
+            MergeDialog { id: "mergeDialog" }
+            ChatFilterBox {}
+            button {
+                class: "bg-raised text-sm",
+                onclick: move |_| {
+                    chats.write().library.new_folder("New folder".to_string(), None);
+                },
+                "New folder"
+            }
+            div { class: "flex flex-col overflow-y-auto",
+                if !pinned.is_empty() {
+                    rsx! {
+                        GroupHeader { label: "Pinned", target: DropTarget::Pinned }
+                        for chat in pinned {
+                            ChatRow { key: "{chat.uuid()}", chat: chat, depth: 0 }
+                        }
+                    }
+                }
+                for folder in folders {
+                    FolderView { key: "{folder}", folder: folder, depth: 0, by_folder: by_folder.clone() }
+                }
+                GroupHeader { label: "Chats", target: DropTarget::Folder(None) }
+                // Branches sit indented under the chat they came from
+                for (chat, depth) in unfiled {
+                    ChatRow { key: "{chat.uuid()}", chat: chat, depth: depth }
+                }
+            }
+        }
+    })
+}
+
+/// Narrows and orders the chat list, arrow keys move through what's left
+fn ChatFilterBox(cx: Scope) -> Element {
+    let app_state = AppState::use_app_context(cx);
+    let chats = app_state.chats;
+    let sidebar = SidebarState::use_context(cx);
+    let show_more = use_state(cx, || false);
+    let library = chats.read().library.clone();
+    let filter = library.filter.clone();
+    let tags = library.tags();
+    let personas: Vec<(Uuid, String)> =
+        app_state.personas.read().iter().map(|(uuid, persona)| (*uuid, persona.name.clone())).collect();
+    let from = filter.from.clone().unwrap_or_default();
+    let to = filter.to.clone().unwrap_or_default();
+    let more_label = if **show_more { "Fewer" } else { "More" };
+
+    let open = move |backwards: bool| {
+        let next = chats.read().cycle_chat(backwards);
+        if let Some(uuid) = next {
+            AppState::set_active_chat(cx, uuid);
+            app_state.page.set(Page::Chat);
+        }
+    };
+    // An empty value clears that part of the filter
+    let set_day = |day: &str| Some(day.to_string()).filter(|day| parse_day(day).is_some());
+
+    cx.render(rsx! {
+        div { class: "flex flex-col gap-1 text-sm py-1",
+            div { class: "flex gap-1",
+                input {
+                    id: "chatFilter",
+                    class: "bg-raised min-w-0 flex-1",
+                    placeholder: "Filter chats",
+                    value: "{filter.text}",
+                    oninput: move |evt| chats.write().library.filter.text = evt.value.clone(),
+                    onkeydown: move |evt| match evt.key() {
+                        Key::ArrowDown => open(false),
+                        Key::ArrowUp => open(true),
+                        Key::Enter => sidebar.open.set(false),
+                        Key::Escape => chats.write().library.filter = ChatFilter::default(),
+                        _ => keep_typing(evt),
+                    },
+                }
+                select {
+                    class: "bg-raised",
+                    title: "Sort chats by",
+                    onchange: move |evt| {
+                        if let Some(sort) = ChatSort::from_name(&evt.value) {
+                            chats.write().library.sort = sort;
+                        }
+                    },
+                    for (sort, name) in ChatSort::ALL.map(|sort| (sort, sort.name())) {
+                        option { value: "{name}", selected: library.sort == sort, "{name}" }
+                    }
+                }
+                button {
+                    class: "bg-raised",
+                    onclick: move |_| show_more.set(!**show_more),
+                    "{more_label}"
+                }
+            }
+            if **show_more {
+                rsx! {
+                    select {
+                        class: "bg-raised",
+                        onchange: move |evt| chats.write().library.filter.persona = evt.value.parse::<Uuid>().ok(),
+                        option { value: "", "Any persona" }
+                        for (uuid, name) in personas {
+                            option { value: "{uuid}", selected: filter.persona == Some(uuid), "{name}" }
+                        }
+                    }
+                    select {
+                        class: "bg-raised",
+                        onchange: move |evt| {
+                            chats.write().library.filter.tag = Some(evt.value.clone()).filter(|tag| !tag.is_empty());
+                        },
+                        option { value: "", "Any tag" }
+                        for name in tags {
+                            option { value: "{name}", selected: filter.tag.as_ref() == Some(&name), "{name}" }
+                        }
+                    }
+                    label { class: "flex gap-1 justify-between",
+                        "From"
+                        input {
+                            class: "bg-raised",
+                            r#type: "date",
+                            value: "{from}",
+                            onchange: move |evt| chats.write().library.filter.from = set_day(&evt.value),
+                        }
+                    }
+                    label { class: "flex gap-1 justify-between",
+                        "To"
+                        input {
+                            class: "bg-raised",
+                            r#type: "date",
+                            value: "{to}",
+                            onchange: move |evt| chats.write().library.filter.to = set_day(&evt.value),
+                        }
+                    }
+                }
+            }
+            if !filter.is_empty() {
+                rsx! {
+                    button {
+                        class: "bg-raised",
+                        onclick: move |_| chats.write().library.filter = ChatFilter::default(),
+                        "Clear filter"
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// A group's heading, which chats can be dropped on
+#[component]
+fn GroupHeader(cx: Scope, label: &'static str, target: DropTarget) -> Element {
+    let chats = AppState::chats(cx);
+    let sidebar = SidebarState::use_context(cx);
+    let target = *target;
+    cx.render(rsx! {
+        div {
+            class: "text-left text-xs uppercase opacity-70 pt-2",
+            "data-drop": "true",
+            ondrop: move |_| sidebar.drop_on(chats, target),
+            "{label}"
+        }
+    })
+}
+
+/// A folder with its subfolders and chats, nested `depth` deep
+#[component]
+fn FolderView(cx: Scope, folder: Uuid, depth: usize, by_folder: Rc<FolderChats>) -> Element {
+    let chats = AppState::chats(cx);
+    let sidebar = SidebarState::use_context(cx);
+    let renaming = use_state(cx, || false);
+    let uuid = *folder;
+    let Some(info) = chats.read().library.folders.get(&uuid).cloned() else {
+        return None;
+    };
+    let toggle = if info.collapsed { "▸" } else { "▾" };
+    let subfolders = chats.read().library.subfolders(Some(uuid));
+    let filed = by_folder.get(&Some(uuid)).cloned().unwrap_or_default();
+    let depth = *depth;
+    cx.render(rsx! {
+        div {
+            class: "flex gap-2 justify-between pt-1",
+            style: "padding-left: {depth * 12}px;",
+            draggable: "true",
+            "data-drop": "true",
+            ondragstart: move |_| sidebar.dragging.set(Some(Dragged::Folder(uuid))),
+            ondrop: move |_| sidebar.drop_on(chats, DropTarget::Folder(Some(uuid))),
+            if **renaming {
+                rsx! {
+                    input {
+                        class: "w-full min-w-0",
+                        value: "{info.name}",
+                        onmounted: move |cx2| {
+                            cx2.inner().set_focus(true);
+                        },
+                        onkeydown: keep_typing,
+                        oninput: move |evt| {
+                            if let Some(folder) = chats.write().library.folders.get_mut(&uuid) {
+                                folder.name = evt.value.clone();
+                            }
+                        },
+                        onkeyup: move |evt| {
+                            if evt.key() == Key::Enter || evt.key() == Key::Escape {
+                                renaming.set(false);
+                            }
+                        },
+                        onblur: move |_| renaming.set(false),
+                    }
+                }
+            } else {
+                rsx! {
+                    button {
+                        class: "text-left font-bold flex-1 min-w-0",
+                        onclick: move |_| {
+                            if let Some(folder) = chats.write().library.folders.get_mut(&uuid) {
+                                folder.collapsed = !folder.collapsed;
+                            }
+                        },
+                        "{toggle} {info.name}"
+                    }
+                }
+            }
+            div { class: "flex gap-2",
+                button {
+                    class: "bg-raised",
+                    title: "New folder inside",
+                    onclick: move |_| {
+                        let mut chats = chats.write();
+                        chats.library.new_folder("New folder".to_string(), Some(uuid));
+                        chats.library.folders[&uuid].collapsed = false;
+                    },
+                    "+"
+                }
+                button {
+                    class: "bg-raised",
+                    title: "Rename folder",
+                    onclick: move |_| renaming.set(true),
+                    "R"
+                }
+                button {
+                    class: "bg-raised",
+                    title: "Delete folder, keeping what's in it",
+                    onclick: move |_| chats.write().library.delete_folder(&uuid),
+                    "x"
+                }
+            }
+        }
+        if !info.collapsed {
+            rsx! {
+                for subfolder in subfolders {
+                    FolderView { key: "{subfolder}", folder: subfolder, depth: depth + 1, by_folder: by_folder.clone() }
+                }
+                for (chat, branch_depth) in filed {
+                    ChatRow { key: "{chat.uuid()}", chat: chat, depth: depth + 1 + branch_depth }
+                }
+            }
+        }
+    })
+}
+
+/// A chat in the sidebar, which can be dragged to another place or folder
+#[component]
+fn ChatRow(cx: Scope, chat: Chat, depth: usize) -> Element {
+    let chats = AppState::chats(cx);
+    let rename = AppState::renaming(cx);
+    let sidebar = SidebarState::use_context(cx);
+    let filing_open = use_state(cx, || false);
+    let eval = use_eval(cx);
+    let chat = *chat;
+    let depth = *depth;
+    let uuid = *chat.uuid();
+    let selected = chats.read().active_chat_uuid() == &Some(uuid);
+    let branch_mark = if chat.branch.is_some() { "↳ " } else { "" };
+    cx.render(rsx! {
+        div {
+            class: "flex gap-2 justify-between",
+            style: "padding-left: {depth * 12}px;",
+            draggable: "true",
+            "data-drop": "true",
+            ondragstart: move |_| sidebar.dragging.set(Some(Dragged::Chat(uuid))),
+            ondrop: move |_| sidebar.drop_on(chats, DropTarget::Chat(uuid)),
+            if *rename.read() && selected {
+                rsx!{
+                    textarea {
+                        class: "w-full max-h-20",
+                        id: "renameChat",
+                        rows: "1",
+                        onmounted: move |cx2| {
+                            cx2.inner().set_focus(true);
+                        },
+                        onkeydown: keep_typing,
+                        oninput: move |evt| {
+                            eval(r#"
+                                el = document.getElementById("renameChat");
+                                el.style.height = "auto";
+                                el.style.height = el.scrollHeight + "px";
+                            "#).unwrap();
+                            if evt.value.ends_with('\n') {
+                                chat.save();
+                                rename.set(false);
+                            } else {
+                                AppState::active_chat(cx).read().unwrap().name.set(evt.value.clone())
+                            }
+                        },
+                        onkeyup: move |evt| {
+                            if evt.key() == Key::Enter {
+                                chat.save();
+                                rename.set(false);
+                            }
+                        },
+                        value: "{chat.name}"
+                    }
+                }
+            } else {
+                let style = if selected { "bg-raised"} else { "" };
+                rsx!{
+                    button {
+                        class: "text-left {style}",
+                        onclick: move |_| {
+                            AppState::set_active_chat(cx, uuid);
+                            AppState::page(cx).set(Page::Chat);
+                            sidebar.open.set(false);
+                        },
+                        "{branch_mark}{chat.name}"
+                    }
+                }
+            }
+            div {
+                class: "flex gap-2",
+                button {
+                    class: "bg-raised",
+                    title: "Folder, tags and pinning",
+                    onclick: move |_| filing_open.set(!**filing_open),
+                    "⋯"
+                }
+                if selected {
+                    rsx!{
+                        button {
+                            class: "bg-raised",
+                            onclick: move |_| rename.set(true),
+                            "R"
+                        }
+                        button {
+                            class: "bg-raised",
+                            title: "Merge another chat into this one",
+                            onclick: move |_| {
+                                eval(r#"document.getElementById("mergeDialog").showModal();"#).unwrap();
+                            },
+                            "M"
+                        }
+                        button {
+                            class: "bg-raised",
+                            onclick: move |_| AppState::delete_active_chat(cx),
+                            "x"
+                        }
+                    }
+                }
+            }
+        }
+        if **filing_open {
+            rsx! { FilingEditor { chat: uuid } }
+        }
+    })
+}
+
+/// Where a chat is filed, what it's tagged and whether it's pinned
+#[component]
+fn FilingEditor(cx: Scope, chat: Uuid) -> Element {
+    let chats = AppState::chats(cx);
+    let uuid = *chat;
+    let filing = chats.read().library.filing(&uuid);
+    let tags = filing.tags.iter().cloned().collect::<Vec<String>>().join(", ");
+    let folders: Vec<(Uuid, String)> = {
+        let chats = chats.read();
+        let mut folders: Vec<(Uuid, String)> =
+            chats.library.folders.keys().map(|folder| (*folder, chats.library.path(folder))).collect();
+        folders.sort_by_key(|(_, path)| path.to_lowercase());
+        folders
+    };
+    cx.render(rsx! {
+        div { class: "flex flex-col gap-1 text-sm bg-raised p-1 mb-1",
+            label { class: "flex gap-2",
+                input {
+                    r#type: "checkbox",
+                    checked: filing.pinned,
+                    onchange: move |evt| {
+                        chats.write().library.filing_mut(uuid).pinned = evt.value == "true";
+                    },
+                }
+                "Pinned"
+            }
+            select {
+                class: "bg-surface",
+                onchange: move |evt| {
+                    let folder = evt.value.parse::<Uuid>().ok();
+                    chats.write().library.file(uuid, folder);
+                },
+                option { value: "", selected: filing.folder.is_none(), "No folder" }
+                for (folder, path) in folders {
+                    option { value: "{folder}", selected: filing.folder == Some(folder), "{path}" }
+                }
+            }
+            input {
+                class: "bg-surface",
+                placeholder: "Tags, separated by commas",
+                value: "{tags}",
+                onkeydown: keep_typing,
+                onchange: move |evt| {
+                    chats.write().library.filing_mut(uuid).tags = parse_tags(&evt.value);
+                },
+            }
+        }
+    })
+}
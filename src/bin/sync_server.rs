@@ -0,0 +1,36 @@
+//! Self-hostable sync server, stores each user's encrypted journal as files on disk.
+//!
+//! ```bash
+//! let-me-talk-sync --addr 0.0.0.0:8754 --data ./sync-data
+//! ```
+#[cfg(not(target_arch = "wasm32"))]
+use let_me_talk::sync::server::{serve, FileStore};
+
+/// Nothing to serve from a browser, this only exists so web builds of the crate succeed
+#[cfg(target_arch = "wasm32")]
+fn main() {}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let mut addr = "127.0.0.1:8754".to_string();
+    let mut data = "sync-data".to_string();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--addr" => addr = args.next().expect("--addr needs a value"),
+            "--data" => data = args.next().expect("--data needs a value"),
+            _ => {
+                eprintln!("usage: let-me-talk-sync [--addr HOST:PORT] [--data DIR]");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let store = FileStore::new(&data).expect("couldn't create the data directory");
+    if let Err(e) = serve(&addr, store) {
+        eprintln!("sync server stopped: {e}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,360 @@
+//! Terminal front end, reads and writes the same storage as the desktop app.
+//!
+//! Keys:
+//! - `Tab`/`Shift+Tab` cycle the persona you're writing as, `Enter` sends
+//! - `Esc` moves focus to the chat list, `Up`/`Down` pick a chat and `Enter` goes back to writing
+//! - `Ctrl+N` starts a new chat, `Ctrl+P` creates a persona, `Ctrl+A` adds an existing persona to the chat
+//! - `Ctrl+C` or `q` in the chat list quits
+#[cfg(target_arch = "wasm32")]
+fn main() {}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> std::io::Result<()> {
+    tui::run()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod tui {
+    use crossterm::{
+        event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+        execute,
+        terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    };
+    use dioxus_std::storage::*;
+    use let_me_talk::{
+        colours::{text_colour_from_bg, Colour, Rgb},
+        data::{cycle_persona, ChatData, Journal, MessageKind},
+    };
+    use ratatui::{
+        prelude::*,
+        widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    };
+    use std::{io, str::FromStr};
+    use uuid::Uuid;
+
+    enum Mode {
+        Chats,
+        Writing,
+        NewPersonaName,
+        NewPersonaColour { name: String },
+        AddPersona { selected: usize },
+    }
+
+    struct App {
+        journal: Journal,
+        selected: usize,
+        mode: Mode,
+        input: String,
+        status: String,
+    }
+
+    fn colour(Rgb(r, g, b): Rgb) -> Color {
+        Color::Rgb(r, g, b)
+    }
+
+    fn persona_style(rgb: Rgb) -> Style {
+        let Colour::Colour(text) | Colour::BgColour(text) = text_colour_from_bg(rgb);
+        Style::default().bg(colour(rgb)).fg(colour(text))
+    }
+
+    impl App {
+        fn new() -> Self {
+            let mut journal = Journal::load();
+            if journal.chats.is_empty() {
+                journal.new_chat();
+            }
+            let selected = journal
+                .active_chat
+                .and_then(|uuid| journal.chats.iter().position(|chat| chat.uuid == uuid))
+                .unwrap_or(0);
+            App {
+                journal,
+                selected,
+                mode: Mode::Writing,
+                input: String::new(),
+                status: String::new(),
+            }
+        }
+
+        fn chat(&self) -> &ChatData {
+            &self.journal.chats[self.selected]
+        }
+
+        fn chat_mut(&mut self) -> &mut ChatData {
+            &mut self.journal.chats[self.selected]
+        }
+
+        fn select(&mut self, index: usize) {
+            self.selected = index;
+            self.journal.active_chat = Some(self.chat().uuid);
+            self.journal.save_index();
+        }
+
+        fn cycle_persona(&mut self, backwards: bool) {
+            let chat = self.chat_mut();
+            chat.active_persona = cycle_persona(&chat.added_personas, &chat.active_persona, backwards);
+        }
+
+        /// Personas not yet in the current chat, candidates for `Ctrl+A`
+        fn addable_personas(&self) -> Vec<Uuid> {
+            self.journal
+                .personas
+                .iter()
+                .map(|(uuid, _)| *uuid)
+                .filter(|uuid| !self.chat().added_personas.contains(uuid))
+                .collect()
+        }
+
+        /// Returns false once the user wants to quit
+        fn on_key(&mut self, key: KeyEvent) -> bool {
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            match (ctrl, key.code) {
+                (true, KeyCode::Char('c')) => return false,
+                (true, KeyCode::Char('n')) => {
+                    self.journal.new_chat();
+                    self.select(self.journal.chats.len() - 1);
+                    self.mode = Mode::Writing;
+                    return true;
+                }
+                (true, KeyCode::Char('p')) => {
+                    self.input.clear();
+                    self.mode = Mode::NewPersonaName;
+                    return true;
+                }
+                (true, KeyCode::Char('a')) => {
+                    if self.addable_personas().is_empty() {
+                        self.status = "Every persona is already in this chat".to_string();
+                    } else {
+                        self.mode = Mode::AddPersona { selected: 0 };
+                    }
+                    return true;
+                }
+                _ => {}
+            }
+
+            let addable = self.addable_personas();
+            match &mut self.mode {
+                Mode::Chats => match key.code {
+                    KeyCode::Char('q') => return false,
+                    KeyCode::Up if self.selected > 0 => self.select(self.selected - 1),
+                    KeyCode::Down if self.selected + 1 < self.journal.chats.len() => self.select(self.selected + 1),
+                    KeyCode::Enter | KeyCode::Right => self.mode = Mode::Writing,
+                    _ => {}
+                },
+                Mode::Writing => match key.code {
+                    KeyCode::Esc => self.mode = Mode::Chats,
+                    KeyCode::Tab => self.cycle_persona(false),
+                    KeyCode::BackTab => self.cycle_persona(true),
+                    KeyCode::Enter if !self.input.is_empty() => {
+                        let msg = std::mem::take(&mut self.input);
+                        let chat = self.chat_mut();
+                        chat.send(chat.active_persona, msg);
+                        chat.save();
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(c) => self.input.push(c),
+                    _ => {}
+                },
+                Mode::NewPersonaName => match key.code {
+                    KeyCode::Esc => self.mode = Mode::Writing,
+                    KeyCode::Enter if !self.input.is_empty() => {
+                        let name = std::mem::take(&mut self.input);
+                        self.mode = Mode::NewPersonaColour { name };
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(c) => self.input.push(c),
+                    _ => {}
+                },
+                Mode::NewPersonaColour { name } => match key.code {
+                    KeyCode::Esc => self.mode = Mode::Writing,
+                    KeyCode::Enter => match Some(self.input.as_str()).filter(|input| !input.is_empty()).map(Rgb::from_str).transpose() {
+                        Ok(rgb) => {
+                            let name = std::mem::take(name);
+                            self.input.clear();
+                            let uuid = self.journal.new_persona(name, rgb);
+                            let chat = self.chat_mut();
+                            chat.add_persona(uuid);
+                            chat.save();
+                            self.mode = Mode::Writing;
+                        }
+                        Err(e) => self.status = e.to_string(),
+                    },
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(c) => self.input.push(c),
+                    _ => {}
+                },
+                Mode::AddPersona { selected } => {
+                    match key.code {
+                        KeyCode::Esc => self.mode = Mode::Writing,
+                        KeyCode::Up if *selected > 0 => *selected -= 1,
+                        KeyCode::Down if *selected + 1 < addable.len() => *selected += 1,
+                        KeyCode::Enter => {
+                            let uuid = addable[*selected];
+                            let chat = self.chat_mut();
+                            chat.add_persona(uuid);
+                            chat.save();
+                            self.mode = Mode::Writing;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            true
+        }
+    }
+
+    fn draw(f: &mut Frame, app: &App) {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(28), Constraint::Min(0)])
+            .split(f.size());
+        let (sidebar, main) = (columns[0], columns[1]);
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1), Constraint::Length(3)])
+            .split(main);
+        let (messages, personas, input) = (rows[0], rows[1], rows[2]);
+
+        // Chat list, like the SideBar
+        let chats: Vec<ListItem> = app
+            .journal
+            .chats
+            .iter()
+            .map(|chat| ListItem::new(chat.name.clone()))
+            .collect();
+        let chat_list = List::new(chats)
+            .block(Block::default().borders(Borders::ALL).title("Chats"))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected(Some(app.selected));
+        f.render_stateful_widget(chat_list, sidebar, &mut state);
+
+        // Messages, grouped under the persona's name like the MessageBox
+        let chat = app.chat();
+        let mut lines = Vec::new();
+        let mut previous = None;
+        for msg in chat.messages.msgs.iter() {
+            match msg.kind {
+                MessageKind::Narration => {
+                    lines.push(Line::from(Span::styled(msg.msg.clone(), Style::default().add_modifier(Modifier::ITALIC))));
+                    previous = None;
+                    continue;
+                }
+                MessageKind::Event => {
+                    lines.push(Line::from(Span::styled(format!("-- {} --", msg.msg), Style::default().add_modifier(Modifier::DIM))));
+                    previous = None;
+                    continue;
+                }
+                MessageKind::Speech | MessageKind::Aside => {}
+            }
+            let Some(persona) = app.journal.personas.get(&msg.persona) else { continue };
+            if previous != Some(msg.persona) {
+                lines.push(Line::from(Span::styled(
+                    persona.name.clone(),
+                    Style::default().fg(colour(persona.colour)).add_modifier(Modifier::BOLD),
+                )));
+                previous = Some(msg.persona);
+            }
+            let style = match msg.kind {
+                MessageKind::Aside => Style::default().fg(colour(persona.colour)).add_modifier(Modifier::ITALIC),
+                _ => persona_style(persona.colour),
+            };
+            lines.push(Line::from(Span::styled(format!(" {} ", msg.msg), style)));
+        }
+        // Keep the latest messages in view
+        let height = messages.height.saturating_sub(2) as usize;
+        let lines = lines.split_off(lines.len().saturating_sub(height));
+        f.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL).title(chat.name.clone())),
+            messages,
+        );
+
+        // Persona bar, like the PersonaSelect
+        let spans: Vec<Span> = chat
+            .added_personas
+            .iter()
+            .filter_map(|uuid| app.journal.personas.get(uuid).map(|persona| (uuid, persona)))
+            .map(|(uuid, persona)| {
+                let style = if *uuid == chat.active_persona {
+                    persona_style(persona.colour).add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+                } else {
+                    Style::default().fg(colour(persona.colour))
+                };
+                Span::styled(format!(" {} ", persona.name), style)
+            })
+            .collect();
+        f.render_widget(Paragraph::new(Line::from(spans)), personas);
+
+        let title = match &app.mode {
+            Mode::Chats => "Up/Down to pick a chat, Enter to write, q to quit".to_string(),
+            Mode::Writing => "Enter to send, Tab to switch persona, Esc for chats".to_string(),
+            Mode::NewPersonaName => "New persona name".to_string(),
+            Mode::NewPersonaColour { name } => format!("Colour for {name}, e.g. #aabbcc, teal or hsl(200 60% 40%), or Enter to pick one"),
+            Mode::AddPersona { .. } => "Up/Down and Enter to add a persona".to_string(),
+        };
+        let title = if app.status.is_empty() { title } else { format!("{title} ({})", app.status) };
+        f.render_widget(
+            Paragraph::new(app.input.as_str()).block(Block::default().borders(Borders::ALL).title(title)),
+            input,
+        );
+
+        if let Mode::AddPersona { selected } = app.mode {
+            let items: Vec<ListItem> = app
+                .addable_personas()
+                .iter()
+                .filter_map(|uuid| app.journal.personas.get(uuid))
+                .map(|persona| ListItem::new(persona.name.clone()).style(Style::default().fg(colour(persona.colour))))
+                .collect();
+            let area = Rect {
+                x: main.x + 2,
+                y: main.y + 1,
+                width: main.width.saturating_sub(4).min(30),
+                height: (items.len() as u16 + 2).min(main.height.saturating_sub(2)),
+            };
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title("Add persona"))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            let mut state = ListState::default().with_selected(Some(selected));
+            f.render_widget(ratatui::widgets::Clear, area);
+            f.render_stateful_widget(list, area, &mut state);
+        }
+    }
+
+    fn event_loop(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> io::Result<()> {
+        loop {
+            terminal.draw(|f| draw(f, app))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                app.status.clear();
+                if !app.on_key(key) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    pub fn run() -> io::Result<()> {
+        set_dir!();
+        let mut app = App::new();
+
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+
+        let result = event_loop(&mut terminal, &mut app);
+
+        disable_raw_mode()?;
+        execute!(io::stdout(), LeaveAlternateScreen)?;
+        app.journal.save_index();
+        result
+    }
+}
@@ -0,0 +1,255 @@
+//! `let-me-talk <command>` works on the desktop storage without opening the window
+use crate::colours::Rgb;
+use crate::data::{ChatData, Guide, Guides, Journal, JournalExport, MessageKind};
+use std::str::FromStr;
+
+const USAGE: &str = "usage: let-me-talk [command]
+
+With no command the app opens as usual.
+
+commands:
+    list                                     list chats
+    show <chat>                              print a chat's transcript
+    say [--chat <chat>] [--kind <kind>] <persona> <message>
+                                             append a message as a persona, to the active chat by default,
+                                             the kind is speech, narration, aside or event
+    new-chat [--template <template>] [name]  start a chat and make it the active one, from a template
+                                             set up in the app's settings if given
+    persona list                             list personas
+    persona new <name> [colour]              create a persona, the colour can be hex, rgb(), hsl() or a CSS name,
+                                             one that stands apart from the others is picked if left out
+    export [--format json|md] [--chat <chat>] [file]
+                                             export everything, or one chat, to a file or stdout
+    import <file>                            add the chats and personas from a json export
+    guide list                               list the guided session scripts
+    guide import <file>                      add the guides from a script file, replacing any with the same names
+    search <text>                            find messages containing text
+
+<chat> is a chat's name or the start of its id, as shown by `list`.
+Flags go before the other arguments, `--` ends them early.";
+
+/// Runs a command, returning the process exit code
+pub fn run(args: Vec<String>) -> i32 {
+    let mut journal = Journal::load();
+    match command(&mut journal, args) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{e}");
+            1
+        }
+    }
+}
+
+fn find_chat<'a>(journal: &'a Journal, chat: &str) -> Result<&'a ChatData, String> {
+    journal
+        .find_chat(chat)
+        .ok_or_else(|| format!("no chat called {chat}, see `let-me-talk list`"))
+}
+
+/// What `command` understands, anything else opens the app
+const COMMANDS: [&str; 12] = [
+    "list", "show", "say", "new-chat", "persona", "export", "import", "guide", "search", "help", "--help", "-h",
+];
+
+/// Whether `arg` names a command, so the app can tell them from arguments meant for itself
+pub fn is_command(arg: &str) -> bool {
+    COMMANDS.contains(&arg)
+}
+
+/// Pulls `--name value` out of the flags, which end at the first positional argument or `--`
+fn take_flag(args: &mut Vec<String>, name: &str) -> Result<Option<String>, String> {
+    let mut i = 0;
+    while let Some(arg) = args.get(i) {
+        if arg == "--" || !arg.starts_with("--") {
+            break;
+        }
+        if arg == name {
+            if i + 1 == args.len() {
+                return Err(format!("{name} needs a value"));
+            }
+            let value = args.remove(i + 1);
+            args.remove(i);
+            return Ok(Some(value));
+        }
+        i += 2;
+    }
+    Ok(None)
+}
+
+/// Once every known flag is taken, drops the `--` ending them and refuses any flag left over
+fn end_flags(args: &mut Vec<String>) -> Result<(), String> {
+    match args.first() {
+        Some(arg) if arg == "--" => {
+            args.remove(0);
+            Ok(())
+        }
+        Some(arg) if arg.starts_with("--") => Err(format!("unknown option {arg}, see `let-me-talk help`")),
+        _ => Ok(()),
+    }
+}
+
+/// Runs a command against `journal`, printing what it finds to stdout
+pub fn command(journal: &mut Journal, mut args: Vec<String>) -> Result<(), String> {
+    if args.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    let command = args.remove(0);
+    match command.as_str() {
+        "list" => {
+            for chat in journal.chats.iter() {
+                let active = if journal.active_chat == Some(chat.uuid) { "*" } else { " " };
+                let id = chat.uuid.to_string();
+                println!("{active} {} {} ({} messages)", &id[..8], chat.name, chat.messages.msgs.len());
+            }
+        }
+        "show" => {
+            if args.is_empty() {
+                return Err("usage: let-me-talk show <chat>".to_string());
+            }
+            let chat = find_chat(journal, &args.join(" "))?;
+            for msg in chat.messages.msgs.iter() {
+                if let Some(parent) = msg.reply_to.and_then(|parent| chat.messages.get(&parent)) {
+                    println!("  ↪ {}: {}", parent.speaker(&journal.personas), parent.preview(60));
+                }
+                match msg.kind {
+                    MessageKind::Narration => println!("[{}]", msg.msg),
+                    MessageKind::Event => println!("-- {} --", msg.msg),
+                    _ => println!("{}: {}", msg.speaker(&journal.personas), msg.msg),
+                }
+            }
+        }
+        "say" => {
+            let chat = match take_flag(&mut args, "--chat")? {
+                Some(chat) => find_chat(journal, &chat)?.uuid,
+                None => journal
+                    .active_chat
+                    .or_else(|| journal.chats.last().map(|chat| chat.uuid))
+                    .ok_or("there are no chats yet, start one with `let-me-talk new-chat`")?,
+            };
+            let kind = match take_flag(&mut args, "--kind")? {
+                Some(kind) => MessageKind::ALL
+                    .into_iter()
+                    .find(|known| known.name().eq_ignore_ascii_case(&kind))
+                    .ok_or_else(|| format!("unknown kind {kind}, use speech, narration, aside or event"))?,
+                None => MessageKind::Speech,
+            };
+            end_flags(&mut args)?;
+            if args.len() < 2 {
+                return Err("usage: let-me-talk say [--chat <chat>] [--kind <kind>] <persona> <message>".to_string());
+            }
+            let name = args.remove(0);
+            let persona = journal
+                .find_persona(&name)
+                .ok_or_else(|| format!("no persona called {name}, create one with `let-me-talk persona new`"))?;
+            let chat = journal.chat_mut(&chat).unwrap();
+            chat.add_persona(persona);
+            chat.post(kind, persona, args.join(" "));
+            chat.save();
+        }
+        "new-chat" => {
+            let template = take_flag(&mut args, "--template")?;
+            end_flags(&mut args)?;
+            let uuid = match template {
+                Some(name) => {
+                    let template = journal
+                        .settings
+                        .find_template(&name)
+                        .ok_or_else(|| format!("no template called {name}, add one in the app's settings"))?;
+                    let chat = template.new_chat(&journal.personas);
+                    journal.start_chat(chat)
+                }
+                None => journal.new_chat(),
+            };
+            if !args.is_empty() {
+                let chat = journal.chat_mut(&uuid).unwrap();
+                chat.name = args.join(" ");
+                chat.save();
+            }
+            println!("{}", journal.chat(&uuid).unwrap().name);
+        }
+        "persona" => match args.first().map(String::as_str) {
+            Some("list") => {
+                for (_, persona) in journal.personas.iter() {
+                    println!("{} {}", persona.colour, persona.name);
+                }
+            }
+            Some("new") if (2..=3).contains(&args.len()) => {
+                if journal.find_persona(&args[1]).is_some() {
+                    return Err(format!("there's already a persona called {}", args[1]));
+                }
+                let colour = match args.get(2) {
+                    Some(colour) => Some(Rgb::from_str(colour).map_err(|e| format!("{colour} isn't a colour: {e}"))?),
+                    None => None,
+                };
+                let uuid = journal.new_persona(args[1].clone(), colour);
+                println!("{} {}", journal.personas.get(&uuid).unwrap().colour, args[1]);
+            }
+            _ => return Err("usage: let-me-talk persona list | persona new <name> [colour]".to_string()),
+        },
+        "export" => {
+            let format = take_flag(&mut args, "--format")?.unwrap_or_else(|| "json".to_string());
+            let chats = match take_flag(&mut args, "--chat")? {
+                Some(chat) => vec![find_chat(journal, &chat)?.clone()],
+                None => journal.chats.clone(),
+            };
+            end_flags(&mut args)?;
+            let out = match format.as_str() {
+                "json" => serde_json::to_string_pretty(&JournalExport {
+                    personas: journal.personas.clone(),
+                    chats,
+                })
+                .unwrap(),
+                "md" => chats
+                    .iter()
+                    .map(|chat| chat.to_markdown(&journal.personas))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                _ => return Err(format!("unknown format {format}, use json or md")),
+            };
+            match args.first() {
+                Some(path) => std::fs::write(path, out).map_err(|e| format!("couldn't write {path}: {e}"))?,
+                None => println!("{out}"),
+            }
+        }
+        "import" => {
+            let path = args.first().ok_or("usage: let-me-talk import <file>")?;
+            let contents = std::fs::read_to_string(path).map_err(|e| format!("couldn't read {path}: {e}"))?;
+            let export: JournalExport =
+                serde_json::from_str(&contents).map_err(|e| format!("{path} isn't a let-me-talk export: {e}"))?;
+            let added = journal.import(export);
+            println!("imported {added} chats");
+        }
+        "guide" => match args.first().map(String::as_str) {
+            Some("list") => {
+                for guide in Guides::load().all() {
+                    println!("{} ({} steps) {}", guide.name, guide.steps.len(), guide.description);
+                }
+            }
+            Some("import") if args.len() == 2 => {
+                let path = &args[1];
+                let contents = std::fs::read_to_string(path).map_err(|e| format!("couldn't read {path}: {e}"))?;
+                let imported = Guide::parse(&contents).map_err(|e| format!("{path}: {e}"))?;
+                let count = imported.len();
+                let mut guides = Guides::load();
+                guides.import(imported);
+                guides.save();
+                println!("imported {count} guides");
+            }
+            _ => return Err("usage: let-me-talk guide list | guide import <file>".to_string()),
+        },
+        "search" => {
+            let query = args.join(" ").to_lowercase();
+            if query.is_empty() {
+                return Err("usage: let-me-talk search <text>".to_string());
+            }
+            for chat in journal.chats.iter() {
+                for msg in chat.messages.msgs.iter().filter(|msg| msg.matches(&query)) {
+                    println!("{}: {}: {}", chat.name, msg.speaker(&journal.personas), msg.msg);
+                }
+            }
+        }
+        "help" | "--help" | "-h" => println!("{USAGE}"),
+        _ => return Err(USAGE.to_string()),
+    }
+    Ok(())
+}
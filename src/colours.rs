@@ -1,31 +1,380 @@
 use serde::{Deserialize, Serialize};
 use std::{fmt::Display, str::FromStr};
 
-#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub mod named;
+pub mod palette;
+pub mod theme;
+pub mod vision;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Rgb(pub u8, pub u8, pub u8);
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct ParseRgbError;
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseRgbError {
+    Empty,
+    /// `#` followed by something other than 3, 4, 6 or 8 digits
+    HexLength(usize),
+    HexDigit(char),
+    UnknownFunction(String),
+    ArgumentCount { function: String, found: usize },
+    InvalidNumber(String),
+    OutOfRange(String),
+    UnknownName(String),
+}
+
+impl Display for ParseRgbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseRgbError::Empty => write!(f, "no colour given"),
+            ParseRgbError::HexLength(len) => {
+                write!(f, "hex colours have 3, 4, 6 or 8 digits, not {len}")
+            }
+            ParseRgbError::HexDigit(c) => write!(f, "'{c}' isn't a hex digit"),
+            ParseRgbError::UnknownFunction(function) => {
+                write!(f, "unknown colour function {function}(), use rgb(), rgba(), hsl() or hsla()")
+            }
+            ParseRgbError::ArgumentCount { function, found } => {
+                write!(f, "{function}() takes 3 values and an optional alpha, not {found}")
+            }
+            ParseRgbError::InvalidNumber(value) => write!(f, "'{value}' isn't a number"),
+            ParseRgbError::OutOfRange(value) => write!(f, "'{value}' is out of range"),
+            ParseRgbError::UnknownName(name) => write!(f, "'{name}' isn't a CSS colour name"),
+        }
+    }
+}
+
+impl std::error::Error for ParseRgbError {}
+
+/// Parses a CSS number, returning the value and whether it was a percentage
+fn parse_number(value: &str) -> Result<(f64, bool), ParseRgbError> {
+    let (number, percent) = match value.strip_suffix('%') {
+        Some(number) => (number, true),
+        None => (value, false),
+    };
+    number
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+        .map(|number| (number, percent))
+        .ok_or_else(|| ParseRgbError::InvalidNumber(value.to_string()))
+}
+
+fn in_range(value: &str, number: f64, max: f64) -> Result<f64, ParseRgbError> {
+    if (0.0..=max).contains(&number) {
+        Ok(number)
+    } else {
+        Err(ParseRgbError::OutOfRange(value.to_string()))
+    }
+}
+
+/// 0-255, or 0%-100%
+fn parse_channel(value: &str) -> Result<u8, ParseRgbError> {
+    let channel = match parse_number(value)? {
+        (number, true) => in_range(value, number, 100.0)? * 2.55,
+        (number, false) => in_range(value, number, 255.0)?,
+    };
+    Ok(channel.round() as u8)
+}
+
+/// 0-1, or 0%-100%
+fn parse_alpha(value: &str) -> Result<f64, ParseRgbError> {
+    match parse_number(value)? {
+        (number, true) => Ok(in_range(value, number, 100.0)? / 100.0),
+        (number, false) => in_range(value, number, 1.0),
+    }
+}
+
+/// Degrees by default, or with a `deg`, `rad` or `turn` unit
+fn parse_hue(value: &str) -> Result<f64, ParseRgbError> {
+    let (number, scale) = if let Some(number) = value.strip_suffix("deg") {
+        (number, 1.0)
+    } else if let Some(number) = value.strip_suffix("rad") {
+        (number, 180.0 / std::f64::consts::PI)
+    } else if let Some(number) = value.strip_suffix("turn") {
+        (number, 360.0)
+    } else {
+        (value, 1.0)
+    };
+    match parse_number(number)? {
+        (hue, false) => Ok((hue * scale).rem_euclid(360.0)),
+        (_, true) => Err(ParseRgbError::InvalidNumber(value.to_string())),
+    }
+}
+
+/// 0%-100%, a bare number is read as a percentage too
+fn parse_percentage(value: &str) -> Result<f64, ParseRgbError> {
+    let (number, _) = parse_number(value)?;
+    Ok(in_range(value, number, 100.0)? / 100.0)
+}
 
+fn parse_hex(hex: &str) -> Result<Rgb, ParseRgbError> {
+    if let Some(c) = hex.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(ParseRgbError::HexDigit(c));
+    }
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).unwrap() * 17;
+    let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+    match hex.len() {
+        3 | 4 => Ok(Rgb(digit(0), digit(1), digit(2))),
+        6 | 8 => Ok(Rgb(pair(0), pair(2), pair(4))),
+        len => Err(ParseRgbError::HexLength(len)),
+    }
+}
+
+fn parse_function(function: &str, args: &str) -> Result<Rgb, ParseRgbError> {
+    // Accepts both `rgb(1, 2, 3, 0.5)` and `rgb(1 2 3 / 50%)`
+    let args: Vec<&str> = args
+        .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|arg| !arg.is_empty())
+        .collect();
+    if !(3..=4).contains(&args.len()) {
+        return Err(ParseRgbError::ArgumentCount {
+            function: function.to_string(),
+            found: args.len(),
+        });
+    }
+    if let Some(alpha) = args.get(3) {
+        parse_alpha(alpha)?;
+    }
+    match function.to_ascii_lowercase().as_str() {
+        "rgb" | "rgba" => Ok(Rgb(
+            parse_channel(args[0])?,
+            parse_channel(args[1])?,
+            parse_channel(args[2])?,
+        )),
+        "hsl" | "hsla" => Ok(Hsl {
+            h: parse_hue(args[0])?,
+            s: parse_percentage(args[1])?,
+            l: parse_percentage(args[2])?,
+        }
+        .into()),
+        _ => Err(ParseRgbError::UnknownFunction(function.to_string())),
+    }
+}
+
+/// Parses any of `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`, `rgba()`,
+/// `hsl()`, `hsla()` or a CSS colour name. Persona colours are opaque so any
+/// alpha is checked and then dropped.
 impl FromStr for Rgb {
     type Err = ParseRgbError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s = s.strip_prefix('#').ok_or(ParseRgbError)?;
-        let (r, g, b) = (
-            s.get(0..2).ok_or(ParseRgbError)?,
-            s.get(2..4).ok_or(ParseRgbError)?,
-            s.get(4..6).ok_or(ParseRgbError)?,
-        );
-        let (r, g, b) = (
-            u8::from_str_radix(r, 16).map_err(|_| ParseRgbError)?,
-            u8::from_str_radix(g, 16).map_err(|_| ParseRgbError)?,
-            u8::from_str_radix(b, 16).map_err(|_| ParseRgbError)?,
-        );
-        Ok(Rgb(r, g, b))
+        let s = s.trim();
+        if s.is_empty() {
+            Err(ParseRgbError::Empty)
+        } else if let Some(hex) = s.strip_prefix('#') {
+            parse_hex(hex)
+        } else if let Some((function, args)) = s.strip_suffix(')').and_then(|s| s.split_once('(')) {
+            parse_function(function.trim(), args)
+        } else {
+            named::named(s).ok_or_else(|| ParseRgbError::UnknownName(s.to_string()))
+        }
+    }
+}
+
+/// `#rrggbb`, what the colour input and [`FromStr`] both understand
+impl Display for Rgb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Rgb(r, g, b) = self;
+        write!(f, "#{r:02x}{g:02x}{b:02x}")
+    }
+}
+
+fn to_linear(c: u8) -> f64 {
+    let c = f64::from(c) / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(c: f64) -> u8 {
+    let c = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Hue in degrees, saturation and lightness from 0 to 1
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Hsl {
+    pub h: f64,
+    pub s: f64,
+    pub l: f64,
+}
+
+impl From<Rgb> for Hsl {
+    fn from(Rgb(r, g, b): Rgb) -> Self {
+        let (r, g, b) = (f64::from(r) / 255.0, f64::from(g) / 255.0, f64::from(b) / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let l = (max + min) / 2.0;
+        let d = max - min;
+        if d == 0.0 {
+            return Hsl { h: 0.0, s: 0.0, l };
+        }
+        let s = d / (1.0 - (2.0 * l - 1.0).abs());
+        let h = if max == r {
+            60.0 * ((g - b) / d).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / d + 2.0)
+        } else {
+            60.0 * ((r - g) / d + 4.0)
+        };
+        Hsl { h, s, l }
+    }
+}
+
+impl From<Hsl> for Rgb {
+    fn from(Hsl { h, s, l }: Hsl) -> Self {
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let h = h.rem_euclid(360.0) / 60.0;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u8 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        let m = l - c / 2.0;
+        let channel = |v: f64| ((v + m).clamp(0.0, 1.0) * 255.0).round() as u8;
+        Rgb(channel(r), channel(g), channel(b))
+    }
+}
+
+impl Display for Hsl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "hsl({:.0} {:.0}% {:.0}%)", self.h, self.s * 100.0, self.l * 100.0)
+    }
+}
+
+/// A perceptual colour space, equal distances look about equally different
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Oklab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+impl Oklab {
+    pub fn distance(self, other: Oklab) -> f64 {
+        ((self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2)).sqrt()
+    }
+}
+
+impl From<Rgb> for Oklab {
+    fn from(Rgb(r, g, b): Rgb) -> Self {
+        let (r, g, b) = (to_linear(r), to_linear(g), to_linear(b));
+        let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+        let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+        let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+        Oklab {
+            l: 0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+            a: 1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+            b: 0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+        }
+    }
+}
+
+/// Colours outside of sRGB are clamped per channel
+impl From<Oklab> for Rgb {
+    fn from(Oklab { l, a, b }: Oklab) -> Self {
+        let l_ = (l + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+        let m_ = (l - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+        let s_ = (l - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+        Rgb(
+            from_linear(4.0767416621 * l_ - 3.3077115913 * m_ + 0.2309699292 * s_),
+            from_linear(-1.2684380046 * l_ + 2.6097574011 * m_ - 0.3413193965 * s_),
+            from_linear(-0.0041960863 * l_ - 0.7034186147 * m_ + 1.7076147010 * s_),
+        )
+    }
+}
+
+/// [`Oklab`] as lightness, chroma and hue in degrees
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Oklch {
+    pub l: f64,
+    pub c: f64,
+    pub h: f64,
+}
+
+impl From<Oklab> for Oklch {
+    fn from(Oklab { l, a, b }: Oklab) -> Self {
+        Oklch {
+            l,
+            c: (a * a + b * b).sqrt(),
+            h: b.atan2(a).to_degrees().rem_euclid(360.0),
+        }
     }
 }
 
+impl From<Oklch> for Oklab {
+    fn from(Oklch { l, c, h }: Oklch) -> Self {
+        let h = h.to_radians();
+        Oklab {
+            l,
+            a: c * h.cos(),
+            b: c * h.sin(),
+        }
+    }
+}
+
+impl From<Rgb> for Oklch {
+    fn from(rgb: Rgb) -> Self {
+        Oklab::from(rgb).into()
+    }
+}
+
+impl From<Oklch> for Rgb {
+    fn from(oklch: Oklch) -> Self {
+        Oklab::from(oklch).into()
+    }
+}
+
+impl Display for Oklch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "oklch({:.1}% {:.3} {:.1})", self.l * 100.0, self.c, self.h)
+    }
+}
+
+/// WCAG AA minimum contrast ratio for normal sized text
+pub const AA_CONTRAST: f64 = 4.5;
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+    pub const WHITE: Rgb = Rgb(255, 255, 255);
+
+    /// WCAG relative luminance, 0 for black up to 1 for white
+    pub fn luminance(self) -> f64 {
+        let Rgb(r, g, b) = self;
+        0.2126 * to_linear(r) + 0.7152 * to_linear(g) + 0.0722 * to_linear(b)
+    }
+
+    /// WCAG contrast ratio between two colours, from 1 up to 21
+    pub fn contrast_ratio(self, other: Rgb) -> f64 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+}
+
+/// Black or white, whichever contrasts most with `bg`, along with the contrast ratio
+pub fn best_text_colour(bg: Rgb) -> (Rgb, f64) {
+    let (black, white) = (bg.contrast_ratio(Rgb::BLACK), bg.contrast_ratio(Rgb::WHITE));
+    if black >= white {
+        (Rgb::BLACK, black)
+    } else {
+        (Rgb::WHITE, white)
+    }
+}
+
+pub fn text_colour_from_bg(bg: Rgb) -> Colour {
+    Colour::Colour(best_text_colour(bg).0)
+}
+
 #[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Colour {
     Colour(Rgb),
@@ -0,0 +1,48 @@
+use super::{Oklab, Oklch, Rgb};
+
+/// Lightness and chroma steps to pick from, mid lightness so either black or
+/// white text stays readable on top
+const LIGHTNESS: [f64; 4] = [0.45, 0.55, 0.65, 0.75];
+const CHROMA: [f64; 3] = [0.08, 0.12, 0.16];
+const HUE_STEP: usize = 15;
+
+/// Candidates that score within this fraction of the best are all fair game when shuffling
+const SHUFFLE_SLACK: f64 = 0.8;
+
+fn candidates() -> impl Iterator<Item = Rgb> {
+    LIGHTNESS.into_iter().flat_map(|l| {
+        CHROMA.into_iter().flat_map(move |c| {
+            (0..360).step_by(HUE_STEP).map(move |h| Oklch { l, c, h: h as f64 }.into())
+        })
+    })
+}
+
+/// Proposes a colour as far as possible, in OKLab, from every colour in `existing`.
+///
+/// Different `seed`s pick between candidates that are nearly as distinct as the
+/// best one, so the same palette can be shuffled.
+pub fn distinct_colour(existing: &[Rgb], seed: u64) -> Rgb {
+    let existing: Vec<Oklab> = existing.iter().map(|rgb| Oklab::from(*rgb)).collect();
+    let scored: Vec<(Rgb, f64)> = candidates()
+        .map(|rgb| {
+            let lab = Oklab::from(rgb);
+            let nearest = existing
+                .iter()
+                .map(|other| lab.distance(*other))
+                .fold(f64::INFINITY, f64::min);
+            (rgb, nearest)
+        })
+        .collect();
+    let best = scored.iter().map(|(_, score)| *score).fold(0.0, f64::max);
+    let good: Vec<Rgb> = scored
+        .into_iter()
+        .filter(|(_, score)| *score >= best * SHUFFLE_SLACK)
+        .map(|(rgb, _)| rgb)
+        .collect();
+    good[(seed % good.len() as u64) as usize]
+}
+
+/// A seed for [`distinct_colour`] that differs every call
+pub fn random_seed() -> u64 {
+    uuid::Uuid::new_v4().as_u128() as u64
+}
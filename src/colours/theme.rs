@@ -0,0 +1,173 @@
+use super::{best_text_colour, vision::Deficiency, Oklch, Rgb};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Persona bubbles are kept within this OKLCH lightness range on dark backgrounds,
+/// bright enough to stand out from the page without glaring
+const DARK_BUBBLE_LIGHTNESS: (f64, f64) = (0.45, 0.8);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeMode {
+    Light,
+    Dark,
+    /// Follows the operating system's `prefers-color-scheme`
+    #[default]
+    System,
+}
+
+impl ThemeMode {
+    pub const ALL: [ThemeMode; 3] = [ThemeMode::Light, ThemeMode::Dark, ThemeMode::System];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ThemeMode::Light => "Light",
+            ThemeMode::Dark => "Dark",
+            ThemeMode::System => "System",
+        }
+    }
+}
+
+/// The colours the UI is drawn with, exposed to Tailwind as `--lmt-*` CSS variables
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThemeColours {
+    /// Behind the chat
+    pub surface: Rgb,
+    /// Header and message input
+    pub panel: Rgb,
+    pub sidebar: Rgb,
+    /// Secondary buttons and the selected chat
+    pub raised: Rgb,
+    pub ink: Rgb,
+    pub accent: Rgb,
+    dark: bool,
+}
+
+impl ThemeColours {
+    pub const LIGHT: ThemeColours = ThemeColours {
+        surface: Rgb(0xf9, 0xfa, 0xfb),
+        panel: Rgb(0xe5, 0xe7, 0xeb),
+        sidebar: Rgb(0xd1, 0xd5, 0xdb),
+        raised: Rgb(0x9c, 0xa3, 0xaf),
+        ink: Rgb(0x11, 0x18, 0x27),
+        accent: Rgb(0x03, 0x07, 0x12),
+        dark: false,
+    };
+
+    pub const DARK: ThemeColours = ThemeColours {
+        surface: Rgb(0x11, 0x18, 0x27),
+        panel: Rgb(0x1f, 0x29, 0x37),
+        sidebar: Rgb(0x03, 0x07, 0x12),
+        raised: Rgb(0x37, 0x41, 0x51),
+        ink: Rgb(0xf3, 0xf4, 0xf6),
+        accent: Rgb(0xe5, 0xe7, 0xeb),
+        dark: true,
+    };
+
+    fn write_vars(&self, css: &mut String) {
+        let (on_accent, _) = best_text_colour(self.accent);
+        let scheme = if self.dark { "dark" } else { "light" };
+        write!(
+            css,
+            ":root {{ color-scheme: {scheme}; --lmt-surface: {}; --lmt-panel: {}; --lmt-sidebar: {}; \
+             --lmt-raised: {}; --lmt-ink: {}; --lmt-accent: {}; --lmt-on-accent: {}; }} \
+             .bubble {{ background-color: var(--bubble-{scheme}); color: var(--bubble-text-{scheme}); }}",
+            self.surface, self.panel, self.sidebar, self.raised, self.ink, self.accent, on_accent
+        )
+        .unwrap();
+    }
+}
+
+/// How the app looks, saved with the rest of the settings
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    pub mode: ThemeMode,
+    /// Replaces the default accent of both the light and dark colours
+    pub accent: Option<Rgb>,
+    /// Shows a shape next to each persona's colour
+    #[serde(default)]
+    pub markers: bool,
+    /// Draws persona colours as they'd look with a colour vision deficiency
+    #[serde(default)]
+    pub simulate: Option<Deficiency>,
+}
+
+impl Theme {
+    fn colours(&self, base: ThemeColours) -> ThemeColours {
+        ThemeColours {
+            accent: self.accent.unwrap_or(base.accent),
+            ..base
+        }
+    }
+
+    /// A persona's colour as it should be drawn, through the simulation if one is on
+    pub fn persona_colour(&self, colour: Rgb) -> Rgb {
+        match self.simulate {
+            Some(deficiency) => deficiency.simulate(colour),
+            None => colour,
+        }
+    }
+
+    /// The text contrast of a persona's bubble as [`bubble_style`] draws it, on the lifted
+    /// colour in dark mode and the lower of the two when following the system
+    pub fn bubble_contrast(&self, colour: Rgb) -> f64 {
+        let colour = self.persona_colour(colour);
+        let contrast = |dark| best_text_colour(bubble_colour(colour, dark)).1;
+        match self.mode {
+            ThemeMode::Light => contrast(false),
+            ThemeMode::Dark => contrast(true),
+            ThemeMode::System => contrast(false).min(contrast(true)),
+        }
+    }
+
+    pub fn light(&self) -> ThemeColours {
+        self.colours(ThemeColours::LIGHT)
+    }
+
+    pub fn dark(&self) -> ThemeColours {
+        self.colours(ThemeColours::DARK)
+    }
+
+    /// The stylesheet defining the theme's variables, for a `style` element at the app's root.
+    /// With [`ThemeMode::System`] the dark colours sit behind a media query so the
+    /// browser switches without us having to ask which scheme is in use.
+    pub fn css(&self) -> String {
+        let mut css = String::new();
+        match self.mode {
+            ThemeMode::Light => self.light().write_vars(&mut css),
+            ThemeMode::Dark => self.dark().write_vars(&mut css),
+            ThemeMode::System => {
+                self.light().write_vars(&mut css);
+                css.push_str(" @media (prefers-color-scheme: dark) { ");
+                self.dark().write_vars(&mut css);
+                css.push_str(" }");
+            }
+        }
+        css
+    }
+}
+
+/// The colour to draw a persona's bubble in, lifting dark colours off a dark background
+pub fn bubble_colour(colour: Rgb, dark: bool) -> Rgb {
+    if !dark {
+        return colour;
+    }
+    let mut lch = Oklch::from(colour);
+    let (min, max) = DARK_BUBBLE_LIGHTNESS;
+    if (min..=max).contains(&lch.l) {
+        return colour;
+    }
+    lch.l = lch.l.clamp(min, max);
+    lch.into()
+}
+
+/// Inline style for an element with the `bubble` class, carrying both the light and
+/// dark versions of a persona's colour so the theme's stylesheet can pick one
+pub fn bubble_style(colour: Rgb) -> String {
+    let light = bubble_colour(colour, false);
+    let dark = bubble_colour(colour, true);
+    format!(
+        "--bubble-light: {light}; --bubble-text-light: {}; --bubble-dark: {dark}; --bubble-text-dark: {};",
+        best_text_colour(light).0,
+        best_text_colour(dark).0
+    )
+}
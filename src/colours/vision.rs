@@ -0,0 +1,74 @@
+use super::{from_linear, to_linear, Oklab, Rgb};
+use serde::{Deserialize, Serialize};
+
+/// Colours closer than this in OKLab are hard to tell apart at a glance
+pub const CONFUSABLE_DISTANCE: f64 = 0.06;
+
+/// Shapes shown alongside persona colours, so identity doesn't rest on hue alone
+const MARKERS: [&str; 10] = ["●", "▲", "■", "◆", "★", "✚", "▼", "⬟", "◐", "✱"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Deficiency {
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+impl Deficiency {
+    pub const ALL: [Deficiency; 3] = [Deficiency::Protanopia, Deficiency::Deuteranopia, Deficiency::Tritanopia];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Deficiency::Protanopia => "Protanopia",
+            Deficiency::Deuteranopia => "Deuteranopia",
+            Deficiency::Tritanopia => "Tritanopia",
+        }
+    }
+
+    /// Machado, Oliveira and Fernandes (2009) at full severity, applied to linear RGB
+    fn matrix(self) -> [[f64; 3]; 3] {
+        match self {
+            Deficiency::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Deficiency::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            Deficiency::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+
+    /// Roughly how `colour` looks to someone with this deficiency
+    pub fn simulate(self, Rgb(r, g, b): Rgb) -> Rgb {
+        let rgb = [to_linear(r), to_linear(g), to_linear(b)];
+        let [r, g, b] = self.matrix().map(|row| row.iter().zip(rgb).map(|(m, c)| m * c).sum::<f64>());
+        Rgb(from_linear(r), from_linear(g), from_linear(b))
+    }
+
+    /// Pairs of indices into `colours` that become hard to tell apart under this deficiency
+    pub fn confusable_pairs(self, colours: &[Rgb]) -> Vec<(usize, usize)> {
+        let simulated: Vec<Oklab> = colours.iter().map(|rgb| Oklab::from(self.simulate(*rgb))).collect();
+        let mut pairs = Vec::new();
+        for (i, a) in simulated.iter().enumerate() {
+            for (j, b) in simulated.iter().enumerate().skip(i + 1) {
+                if a.distance(*b) < CONFUSABLE_DISTANCE {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        pairs
+    }
+}
+
+/// The shape marker for the persona at `index`, repeating once they run out
+pub fn marker(index: usize) -> &'static str {
+    MARKERS[index % MARKERS.len()]
+}
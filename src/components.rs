@@ -2,16 +2,31 @@ use std::str::FromStr;
 
 use crate::colours::*;
 use crate::data::*;
+use crate::storage;
+use crate::sync::{remote, SyncDoc};
 use dioxus::html::input_data::keyboard_types::Key;
 use dioxus::prelude::*;
 use dioxus_signals::Signal;
 use uuid::Uuid;
 
-pub fn text_colour_from_bg(Rgb(r, g, b): Rgb) -> Colour {
-    if (u16::from(r) + u16::from(g) + u16::from(b)) >= (255 * 3 / 2) {
-        Colour::Colour(Rgb(0, 0, 0))
-    } else {
-        Colour::Colour(Rgb(255, 255, 255))
+/// JavaScript that has the browser save `contents` as a file
+pub fn download_js(filename: &str, mime: &str, contents: &str) -> String {
+    let data = serde_json::to_string(contents).unwrap();
+    let filename = serde_json::to_string(filename).unwrap();
+    format!(r#"
+        const blob = new Blob([{data}], {{ type: "{mime}" }});
+        const link = document.createElement("a");
+        link.href = URL.createObjectURL(blob);
+        link.download = {filename};
+        link.click();
+        URL.revokeObjectURL(link.href);
+    "#)
+}
+
+/// Stops keys that type from reaching the app-wide shortcuts, so `?` can be written in text fields
+pub fn keep_typing(evt: KeyboardEvent) {
+    if Shortcut::from_event(&evt.key(), evt.modifiers()).types() {
+        evt.stop_propagation();
     }
 }
 
@@ -20,20 +35,22 @@ pub fn PersonaButton<'a>(
     cx: Scope,
     name: String,
     colour: Rgb,
+    marker: Option<&'static str>,
     onclick: EventHandler<'a, MouseEvent>,
 ) -> Element {
     cx.render(rsx! {
         div { class: "flex flex-col items-center w-auto h-auto leading-none",
-            button { onclick: move |evt| onclick.call(evt), PersonaIcon { colour: *colour } }
+            button { onclick: move |evt| onclick.call(evt), PersonaIcon { colour: *colour, marker: *marker } }
             p { class: "text-xs whitespace-nowrap", "{name}" }
         }
     })
 }
 
+/// A speech bubble in the persona's colour, with their shape marker on it if given
 #[component]
-pub fn PersonaIcon(cx: Scope, colour: Rgb) -> Element {
+pub fn PersonaIcon(cx: Scope, colour: Rgb, marker: Option<&'static str>) -> Element {
     cx.render(rsx! {
-        div {
+        div { class: "relative",
             svg {
                 view_box: "0 0 24 24",
                 xmlns: "http://www.w3.org/2000/svg",
@@ -46,6 +63,45 @@ pub fn PersonaIcon(cx: Scope, colour: Rgb) -> Element {
                     d: "M4.848 2.771A49.144 49.144 0 0112 2.25c2.43 0 4.817.178 7.152.52 1.978.292 3.348 2.024 3.348 3.97v6.02c0 1.946-1.37 3.678-3.348 3.97a48.901 48.901 0 01-3.476.383.39.39 0 00-.297.17l-2.755 4.133a.75.75 0 01-1.248 0l-2.755-4.133a.39.39 0 00-.297-.17 48.9 48.9 0 01-3.476-.384c-1.978-.29-3.348-2.024-3.348-3.97V6.741c0-1.946 1.37-3.68 3.348-3.97z"
                 }
             }
+            if let Some(marker) = marker {
+                rsx! {
+                    span {
+                        class: "absolute inset-x-0 top-1.5 text-center text-xs leading-none",
+                        style: "{text_colour_from_bg(*colour)}",
+                        "{marker}"
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Each core emotion gets a hue of its own, custom ones stay grey
+pub fn emotion_colour(name: &str) -> String {
+    match core_emotion(name).and_then(|core| EMOTION_WHEEL.iter().position(|(other, _)| *other == core)) {
+        Some(index) => format!("hsl({}, 60%, 45%)", index * 360 / EMOTION_WHEEL.len()),
+        None => "gray".to_string(),
+    }
+}
+
+/// The emotions a message is tagged with, stronger ones more opaque
+#[component]
+pub fn EmotionChips(cx: Scope, emotions: Vec<EmotionTag>) -> Element {
+    let chips = emotions.iter().map(|tag| {
+        let style = format!("background-color: {}; opacity: {};", emotion_colour(&tag.name), 0.5 + tag.intensity as f64 / 10.0);
+        (tag.name.clone(), tag.intensity, style)
+    });
+
+    cx.render(rsx! {
+        div { class: "flex flex-wrap gap-1 text-xs",
+            for (name, intensity, style) in chips {
+                span {
+                    class: "rounded-full px-2 text-white",
+                    style: "{style}",
+                    title: "Intensity {intensity} of 5",
+                    "{name} {intensity}"
+                }
+            }
         }
     })
 }
@@ -53,7 +109,7 @@ pub fn PersonaIcon(cx: Scope, colour: Rgb) -> Element {
 #[component]
 pub fn AddPersonaButton<'a>(cx: Scope, onclick: EventHandler<'a, MouseEvent>) -> Element {
     cx.render(rsx! {
-        div { class: "flex flex-col items-center text-black justify-end w-auto h-auto leading-none mr-3",
+        div { class: "flex flex-col items-center text-ink justify-end w-auto h-auto leading-none mr-3",
             button { class: "leading-none", onclick: |evt| onclick.call(evt), AddPersonaIcon {} }
             span { class: "text-xs", "Add" }
         }
@@ -92,20 +148,28 @@ fn AddPersonaIcon(cx: Scope) -> Element {
 #[component]
 pub fn AddNewPersonaDialog<'a>(cx: Scope, id: &'a str, on_create: EventHandler<'a, (String, Rgb)>) -> Element {
     let new_persona_name = use_state(cx, String::new);
-    let new_persona_colour: &UseState<Rgb> = use_state(cx, Rgb::default);
-
     let personas = AppState::personas(cx);
+    let new_persona_colour: &UseState<Rgb> =
+        use_state(cx, || personas.read().suggest_colour(palette::random_seed()));
+    let colour_error = use_state(cx, String::new);
+    // The next persona shouldn't be offered the colour that was just taken
+    let suggest_next = move || new_persona_colour.set(personas.read().suggest_colour(palette::random_seed()));
+    let current = AppState::settings(cx).read().theme;
+    let shown_colour = current.persona_colour(*new_persona_colour.get());
+    let contrast = current.bubble_contrast(*new_persona_colour.get());
 
     cx.render(rsx! {
-        dialog { id: "{id}", class: "p-4 pt-7 rounded-2xl",
+        dialog { id: "{id}", class: "p-4 pt-7 rounded-2xl bg-surface text-ink",
             // div within dialog to prevent display: flex causing dialog to show even when not open
             div { class: "flex flex-col gap-2",
                 input {
                     placeholder: "Persona Name",
+                    onkeydown: keep_typing,
                     oninput: move |evt| { new_persona_name.set(evt.value.clone()) },
                     onkeyup: move |evt| {
                         if evt.key() == Key::Enter && !new_persona_name.current().is_empty() {
                             on_create.call((new_persona_name.current().to_string(), *new_persona_colour.current()));
+                            suggest_next();
                             use_eval(cx)(&format!(r#"document.getElementById("{id}").close();"#));
                         }
                     },
@@ -115,13 +179,50 @@ pub fn AddNewPersonaDialog<'a>(cx: Scope, id: &'a str, on_create: EventHandler<'
                     "Choose a colour: "
                     input {
                         r#type: "color",
-                        onchange: move |evt| new_persona_colour.set(Rgb::from_str(&evt.value).unwrap())
+                        onchange: move |evt| new_persona_colour.set(Rgb::from_str(&evt.value).unwrap()),
+                        value: "{new_persona_colour.get()}"
+                    }
+                    input {
+                        placeholder: "or type one, e.g. teal, #3a7 or hsl(200 60% 40%)",
+                        onkeydown: keep_typing,
+                        oninput: move |evt| match Rgb::from_str(&evt.value) {
+                            Ok(colour) => {
+                                new_persona_colour.set(colour);
+                                colour_error.set(String::new());
+                            }
+                            Err(e) if evt.value.trim().is_empty() => colour_error.set(String::new()),
+                            Err(e) => colour_error.set(e.to_string()),
+                        }
+                    }
+                    if !colour_error.get().is_empty() {
+                        rsx! { p { class: "text-xs text-red-700", "{colour_error}" } }
+                    }
+                }
+                div { class: "flex gap-2 items-center",
+                    div {
+                        class: "bubble rounded-lg px-2 py-1 w-fit text-left",
+                        style: "{theme::bubble_style(shown_colour)}",
+                        "Text contrast {contrast:.1}:1"
+                    }
+                    button {
+                        class: "bg-raised px-2 rounded-lg",
+                        title: "Suggest another colour that stands apart from your personas",
+                        onclick: move |_| suggest_next(),
+                        "Shuffle"
+                    }
+                }
+                if contrast < AA_CONTRAST {
+                    rsx! {
+                        p { class: "text-xs text-red-700",
+                            "Messages in this colour won't be easy to read, try a lighter or darker shade"
+                        }
                     }
                 }
                 button {
-                    class: "w-full bg-gray-950 hover:bg-gray-800 text-white font-bold py-2 px-4 shadow rounded-xl",
+                    class: "w-full bg-accent hover:opacity-90 text-on-accent font-bold py-2 px-4 shadow rounded-xl",
                     onclick: move |_| {
                         on_create.call((new_persona_name.current().to_string(), *new_persona_colour.current()));
+                        suggest_next();
                         use_eval(cx)(&format!(r#"document.getElementById("{id}").close();"#));
                     },
                     AddNewPersonaButton {}
@@ -166,3 +267,440 @@ pub fn SendIcon(cx: Scope) -> Element {
         }
     })
 }
+
+/// Merges this device's chats and personas with a sync file shared between devices
+#[component]
+pub fn SyncDialog<'a>(cx: Scope, id: &'a str) -> Element {
+    let status = use_state(cx, String::new);
+
+    cx.render(rsx! {
+        dialog { id: "{id}", class: "p-4 pt-7 rounded-2xl bg-surface text-ink",
+            div { class: "flex flex-col gap-2",
+                "Sync with another device"
+                SyncFileControls { status: status.clone() }
+                "Or through a sync server"
+                SyncServerControls { status: status.clone() }
+                if !status.get().is_empty() {
+                    rsx! { p { class: "text-sm", "{status}" } }
+                }
+                button {
+                    class: "bg-raised",
+                    onclick: move |_| {
+                        status.set(String::new());
+                        use_eval(cx)(&format!(r#"document.getElementById("{id}").close();"#)).unwrap();
+                    },
+                    "Close"
+                }
+            }
+        }
+    })
+}
+
+/// Finds messages across every chat, picking one opens its chat
+#[component]
+pub fn SearchDialog<'a>(cx: Scope, id: &'a str) -> Element {
+    let chats = AppState::chats(cx);
+    let personas = AppState::personas(cx);
+    let query = use_state(cx, String::new);
+    let needle = query.get().to_lowercase();
+    let close = move || {
+        query.set(String::new());
+        use_eval(cx)(&format!(r#"document.getElementById("{id}").close();"#)).unwrap();
+    };
+
+    let mut results = Vec::new();
+    if !needle.is_empty() {
+        for chat in chats.read().chats() {
+            for msg in chat.messages.read().msgs.iter().filter(|msg| msg.matches(&needle)) {
+                let name = msg.speaker(&personas.read());
+                results.push((*chat.uuid(), msg.uuid, chat.name.read().clone(), name, msg.msg.clone()));
+            }
+        }
+    }
+
+    cx.render(rsx! {
+        dialog { id: "{id}", class: "p-4 pt-7 rounded-2xl bg-surface text-ink w-full max-w-xl",
+            div { class: "flex flex-col gap-2",
+                input {
+                    class: "bg-panel px-2 rounded-lg",
+                    placeholder: "Search messages",
+                    value: "{query}",
+                    onkeydown: keep_typing,
+                    oninput: move |evt| query.set(evt.value.clone()),
+                }
+                div { class: "flex flex-col gap-1 max-h-96 overflow-y-auto text-left",
+                    results.into_iter().map(|(chat, msg, chat_name, persona, text)| rsx! {
+                        button {
+                            key: "{msg}",
+                            class: "text-left hover:bg-panel rounded-lg px-2",
+                            onclick: move |_| {
+                                AppState::set_active_chat(cx, chat);
+                                AppState::page(cx).set(crate::pages::Page::Chat);
+                                close();
+                            },
+                            span { class: "text-xs", "{chat_name}" }
+                            p { b { "{persona}: " } "{text}" }
+                        }
+                    })
+                }
+                button {
+                    class: "bg-raised",
+                    onclick: move |_| close(),
+                    "Close"
+                }
+            }
+        }
+    })
+}
+
+/// Folds another chat into the active one, by send time or at the end
+#[component]
+pub fn MergeDialog<'a>(cx: Scope, id: &'a str) -> Element {
+    let app_state = AppState::use_app_context(cx);
+    let order = use_state(cx, || MergeOrder::Interleave);
+    let active = *app_state.active_chat.read();
+    let others: Vec<(Uuid, String)> = app_state
+        .chats
+        .read()
+        .chats()
+        .filter(|chat| Some(**chat) != active)
+        .map(|chat| (*chat.uuid(), chat.name.read().clone()))
+        .collect();
+    let close = move || {
+        use_eval(cx)(&format!(r#"document.getElementById("{id}").close();"#)).unwrap();
+    };
+
+    cx.render(rsx! {
+        dialog { id: "{id}", class: "p-4 pt-7 rounded-2xl bg-surface text-ink w-full max-w-md",
+            div { class: "flex flex-col gap-2 text-left",
+                h2 { class: "text-xl font-bold", "Merge into this chat" }
+                div { class: "flex gap-2",
+                    for (option, label) in [(MergeOrder::Interleave, "By time sent"), (MergeOrder::Append, "At the end")] {
+                        button {
+                            class: if *order.get() == option { "bg-accent text-on-accent px-2 rounded-lg" } else { "bg-raised px-2 rounded-lg" },
+                            onclick: move |_| order.set(option),
+                            "{label}"
+                        }
+                    }
+                }
+                p { class: "text-xs", "The chat you pick is taken out of the sidebar once its messages are in this one" }
+                div { class: "flex flex-col gap-1 max-h-96 overflow-y-auto",
+                    others.into_iter().map(|(uuid, name)| rsx! {
+                        button {
+                            key: "{uuid}",
+                            class: "text-left hover:bg-panel rounded-lg px-2",
+                            title: "Merge {name} into this chat",
+                            onclick: move |_| {
+                                if let Some(into) = active {
+                                    AppState::merge_chats(cx, into, uuid, *order.get());
+                                }
+                                close();
+                            },
+                            "{name}"
+                        }
+                    })
+                }
+                button {
+                    class: "bg-raised",
+                    onclick: move |_| close(),
+                    "Close"
+                }
+            }
+        }
+    })
+}
+
+/// Every shortcut and what it does, shown on `?`
+#[component]
+pub fn ShortcutsOverlay<'a>(cx: Scope, on_close: EventHandler<'a, ()>) -> Element {
+    let settings = AppState::settings(cx);
+    let keymap = settings.read().keymap.clone();
+
+    cx.render(rsx! {
+        div {
+            class: "fixed inset-0 flex items-center justify-center bg-black/50 z-10",
+            onclick: move |_| on_close.call(()),
+            div { class: "flex flex-col gap-1 p-4 rounded-2xl bg-surface text-ink text-left max-h-full overflow-y-auto",
+                h2 { class: "text-xl font-bold", "Shortcuts" }
+                for binding in keymap.bindings.iter() {
+                    div { class: "flex gap-4 justify-between",
+                        span { "{binding.action.description()}" }
+                        kbd { class: "bg-panel px-2 rounded", "{binding.shortcut}" }
+                    }
+                }
+                p { class: "text-xs", "Change them in Settings, Esc or click to close" }
+            }
+        }
+    })
+}
+
+/// Fuzzy finds chats, personas and actions, ranked by how often and recently each was picked
+#[component]
+pub fn CommandPalette<'a>(cx: Scope, on_close: EventHandler<'a, ()>, on_action: EventHandler<'a, Action>) -> Element {
+    let app_state = AppState::use_app_context(cx);
+    let query = use_state(cx, String::new);
+    let selected = use_state(cx, || 0usize);
+    let usage = use_ref(cx, CommandUsage::load);
+    let now = chrono::Utc::now().timestamp();
+    let active_chat = *app_state.active_chat.read();
+
+    let mut commands = vec![
+        Command::Run(Action::NewChat),
+        Command::Run(Action::OpenSettings),
+        Command::Run(Action::Search),
+        Command::Run(Action::FilterChats),
+        Command::Run(Action::ShowShortcuts),
+    ];
+    if active_chat.is_some() {
+        commands.extend([Command::Run(Action::Rename), Command::Run(Action::ExportChat), Command::Run(Action::DeleteChat)]);
+        commands.extend(app_state.personas.read().iter().map(|(uuid, _)| Command::Persona(*uuid)));
+    }
+    commands.extend(app_state.chats.read().chats().map(|chat| Command::OpenChat(*chat.uuid())));
+    let labelled = commands
+        .into_iter()
+        .map(|command| {
+            let label = match command {
+                Command::OpenChat(uuid) => app_state.chats.read().chats().find(|chat| *chat.uuid() == uuid).map(|chat| chat.name.read().clone()).unwrap_or_default(),
+                Command::Persona(uuid) => app_state.personas.read().get(&uuid).map(|persona| persona.name.clone()).unwrap_or_default(),
+                Command::Run(action) => action.description(),
+            };
+            (command, label)
+        })
+        .collect();
+    let results: Vec<(Command, String)> = usage.read().rank(query.get(), labelled, now).into_iter().take(12).collect();
+    let selected_index = (*selected.get()).min(results.len().saturating_sub(1));
+
+    let run = move |command: Command| {
+        usage.with_mut(|usage| {
+            usage.record(&command, chrono::Utc::now().timestamp());
+            usage.save();
+        });
+        match command {
+            Command::OpenChat(uuid) => {
+                AppState::set_active_chat(cx, uuid);
+                app_state.page.set(crate::pages::Page::Chat);
+            }
+            Command::Persona(uuid) => {
+                if let Some(chat) = *app_state.active_chat.read() {
+                    chat.add_persona(uuid);
+                    chat.save();
+                }
+            }
+            Command::Run(action) => on_action.call(action),
+        }
+        on_close.call(());
+    };
+    let kind = |command: &Command| match command {
+        Command::OpenChat(_) => "Chat",
+        Command::Persona(uuid) => match active_chat {
+            Some(chat) if chat.added_personas.read().contains(uuid) => "Speak as",
+            _ => "Add to chat",
+        },
+        Command::Run(_) => "Action",
+    };
+
+    cx.render(rsx! {
+        div {
+            class: "fixed inset-0 flex items-start justify-center pt-24 bg-black/50 z-10",
+            onclick: move |_| on_close.call(()),
+            div {
+                class: "flex flex-col gap-1 p-2 w-full max-w-xl rounded-2xl bg-surface text-ink text-left",
+                onclick: move |evt| evt.stop_propagation(),
+                input {
+                    class: "bg-panel px-2 py-1 rounded-lg",
+                    placeholder: "Jump to a chat, persona or action",
+                    value: "{query}",
+                    onmounted: move |cx2| {
+                        cx2.inner().set_focus(true);
+                    },
+                    oninput: move |evt| {
+                        query.set(evt.value.clone());
+                        selected.set(0);
+                    },
+                    onkeydown: {
+                        let results = results.clone();
+                        move |evt: KeyboardEvent| {
+                            // Nothing typed here should trigger the app's shortcuts
+                            evt.stop_propagation();
+                            let count = results.len();
+                            match evt.key() {
+                                Key::ArrowDown if count > 0 => selected.set((selected_index + 1) % count),
+                                Key::ArrowUp if count > 0 => selected.set((selected_index + count - 1) % count),
+                                Key::Enter => {
+                                    if let Some((command, _)) = results.get(selected_index) {
+                                        run(*command);
+                                    }
+                                }
+                                Key::Escape => on_close.call(()),
+                                _ => {}
+                            }
+                        }
+                    },
+                }
+                results.iter().enumerate().map(|(i, (command, label))| {
+                    let command = *command;
+                    let style = if i == selected_index { "bg-raised" } else { "" };
+                    rsx! {
+                        button {
+                            key: "{command.key()}",
+                            class: "flex justify-between gap-2 px-2 rounded-lg text-left {style}",
+                            onclick: move |_| run(command),
+                            span { "{label}" }
+                            span { class: "text-xs", "{kind(&command)}" }
+                        }
+                    }
+                })
+            }
+        }
+    })
+}
+
+/// Pushes and pulls through a self-hosted `let-me-talk-sync` server
+#[component]
+fn SyncServerControls(cx: Scope, status: UseState<String>) -> Element {
+    let app_state = AppState::use_app_context(cx);
+    let config = use_state(cx, remote::RemoteConfig::load);
+    let syncing = use_state(cx, || false);
+    let label = if *syncing.get() { "Syncing..." } else { "Sync with server" };
+
+    cx.render(rsx! {
+        input {
+            placeholder: "Server, e.g. http://192.168.1.10:8754",
+            onkeydown: keep_typing,
+            oninput: move |evt| config.with_mut(|config| config.server = evt.value.clone()),
+            value: "{config.server}"
+        }
+        input {
+            placeholder: "User",
+            onkeydown: keep_typing,
+            oninput: move |evt| config.with_mut(|config| config.user = evt.value.clone()),
+            value: "{config.user}"
+        }
+        input {
+            r#type: "password",
+            placeholder: "Token",
+            onkeydown: keep_typing,
+            oninput: move |evt| config.with_mut(|config| config.token = evt.value.clone()),
+            value: "{config.token}"
+        }
+        input {
+            r#type: "password",
+            placeholder: "Passphrase",
+            onkeydown: keep_typing,
+            oninput: move |evt| config.with_mut(|config| config.passphrase = evt.value.clone()),
+            value: "{config.passphrase}"
+        }
+        button {
+            class: "bg-raised",
+            disabled: *syncing.get(),
+            onclick: move |_| {
+                let status = status.clone();
+                let config = config.clone();
+                let syncing = syncing.clone();
+                syncing.set(true);
+                cx.spawn(async move {
+                    let mut current = config.get().clone();
+                    match remote::sync_remote(app_state, &mut current).await {
+                        Ok(()) => status.set("Synced with the server".to_string()),
+                        Err(e) => status.set(format!("Couldn't sync: {e}")),
+                    }
+                    config.set(current);
+                    syncing.set(false);
+                });
+            },
+            "{label}"
+        }
+    })
+}
+
+/// Desktop syncs by reading and writing back a file at a path, e.g. in a shared folder
+#[cfg(not(target_arch = "wasm32"))]
+#[component]
+fn SyncFileControls(cx: Scope, status: UseState<String>) -> Element {
+    let app_state = AppState::use_app_context(cx);
+    let sync_path = use_state(cx, || storage::retrieve("ifs_sync_path", String::new));
+
+    let sync = move |_| {
+        let path = sync_path.get().clone();
+        if path.is_empty() {
+            status.set("Choose where to keep the sync file first".to_string());
+            return;
+        }
+        storage::store("ifs_sync_path", path.clone());
+
+        let remote = match std::fs::read_to_string(&path) {
+            Ok(contents) => match SyncDoc::from_file_str(&contents) {
+                Ok(doc) => Some(doc),
+                Err(e) => {
+                    status.set(format!("Couldn't read the sync file: {e}"));
+                    return;
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                status.set(format!("Couldn't open the sync file: {e}"));
+                return;
+            }
+        };
+        let doc = app_state.sync(remote.as_ref());
+        match std::fs::write(&path, doc.to_file_string()) {
+            Ok(()) => status.set("Synced".to_string()),
+            Err(e) => status.set(format!("Couldn't write the sync file: {e}")),
+        }
+    };
+
+    cx.render(rsx! {
+        input {
+            placeholder: "Sync file path",
+            onkeydown: keep_typing,
+            oninput: move |evt| sync_path.set(evt.value.clone()),
+            value: "{sync_path}"
+        }
+        button { class: "bg-raised", onclick: sync, "Sync" }
+    })
+}
+
+/// The browser can't write back to a file, so the merged result is downloaded instead
+#[cfg(target_arch = "wasm32")]
+#[component]
+fn SyncFileControls(cx: Scope, status: UseState<String>) -> Element {
+    let app_state = AppState::use_app_context(cx);
+    let eval = use_eval(cx);
+
+    cx.render(rsx! {
+        div { class: "flex flex-col gap-0",
+            "Merge a sync file: "
+            input {
+                r#type: "file",
+                accept: ".json",
+                onchange: move |evt| {
+                    let status = status.clone();
+                    cx.spawn(async move {
+                        let Some(engine) = &evt.files else { return };
+                        for name in engine.files() {
+                            let Some(contents) = engine.read_file_to_string(&name).await else {
+                                status.set(format!("Couldn't read {name}"));
+                                return;
+                            };
+                            match SyncDoc::from_file_str(&contents) {
+                                Ok(remote) => {
+                                    app_state.sync(Some(&remote));
+                                    status.set(format!("Merged {name}"));
+                                }
+                                Err(e) => status.set(format!("Couldn't read {name}: {e}")),
+                            }
+                        }
+                    });
+                }
+            }
+        }
+        button {
+            class: "bg-raised",
+            onclick: move |_| {
+                let doc = app_state.sync(None);
+                eval(&download_js("let-me-talk-sync.json", "application/json", &doc.to_file_string())).unwrap();
+            },
+            "Download sync file"
+        }
+    })
+}
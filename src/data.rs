@@ -1,5 +1,7 @@
 use crate::colours::{Colour, Rgb};
+use crate::pages::Page;
 use crate::storage::*;
+use crate::sync::SyncDoc;
 use dioxus::prelude::*;
 use dioxus_signals::{use_signal, Signal};
 use indexmap::{indexmap, indexset, IndexMap, IndexSet};
@@ -7,21 +9,48 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 pub mod chats;
+pub mod checkins;
+pub mod commands;
+pub mod emotions;
+pub mod folders;
+pub mod guides;
+pub mod journal;
+pub mod keymap;
 pub mod personas;
+pub mod settings;
+pub mod slash;
+pub mod templates;
 
 pub use chats::*;
+pub use checkins::*;
+pub use commands::*;
+pub use emotions::*;
+pub use folders::*;
+pub use guides::*;
+pub use journal::*;
+pub use keymap::*;
 pub use personas::*;
+pub use settings::*;
+pub use slash::*;
+pub use templates::*;
 
 #[derive(Clone, Copy, Default)]
 pub struct AppState {
-    personas: Signal<Personas>,
-    chats: Signal<Chats>,
-    active_chat: Signal<Option<Chat>>,
+    pub personas: Signal<Personas>,
+    pub chats: Signal<Chats>,
+    pub active_chat: Signal<Option<Chat>>,
+    pub settings: Signal<Settings>,
+    /// Imported scripts for guided sessions, the built in ones come from [`Guides::all`]
+    pub guides: Signal<Guides>,
+    /// What the main area shows
+    pub page: Signal<Page>,
+    /// Whether the active chat's name is being edited in the sidebar
+    pub renaming: Signal<bool>,
 }
 
 /// Ties together the different types of state
 impl AppState {
-    fn use_app_context(cx: &ScopeState) -> Self {
+    pub fn use_app_context(cx: &ScopeState) -> Self {
         *use_context(cx).expect("no app context provided, must be loaded first")
     }
 
@@ -33,6 +62,22 @@ impl AppState {
         AppState::use_app_context(cx).chats
     }
 
+    pub fn settings(cx: &ScopeState) -> Signal<Settings> {
+        AppState::use_app_context(cx).settings
+    }
+
+    pub fn guides(cx: &ScopeState) -> Signal<Guides> {
+        AppState::use_app_context(cx).guides
+    }
+
+    pub fn page(cx: &ScopeState) -> Signal<Page> {
+        AppState::use_app_context(cx).page
+    }
+
+    pub fn renaming(cx: &ScopeState) -> Signal<bool> {
+        AppState::use_app_context(cx).renaming
+    }
+
     pub fn save_active_chat(cx: &ScopeState) {
         AppState::chats(cx).read().save_active();
     }
@@ -58,14 +103,82 @@ impl AppState {
         AppState::use_app_context(cx).active_chat.set(chats.read().active_chat().copied());
     }
 
+    /// Starts a chat as the settings ask and shows it
+    pub fn start_chat(cx: &ScopeState) {
+        let app_state = AppState::use_app_context(cx);
+        let chat = app_state.settings.read().new_chat(&app_state.personas.read());
+        AppState::new_chat(cx, Chat::from(chat));
+        app_state.page.set(Page::Chat);
+    }
+
+    /// Starts a chat from a template and shows it
+    pub fn start_chat_from(cx: &ScopeState, template: &ChatTemplate) {
+        let app_state = AppState::use_app_context(cx);
+        let chat = template.new_chat(&app_state.personas.read());
+        let chat = Chat::from(chat);
+        chat.save();
+        AppState::new_chat(cx, chat);
+        app_state.page.set(Page::Chat);
+    }
+
+    /// Starts a branch of `chat` from `message` and shows it
+    pub fn branch_chat(cx: &ScopeState, chat: Chat, message: Uuid) {
+        if let Some(branch) = ChatData::from(&chat).branch_from(&message) {
+            let branch = Chat::from(branch);
+            branch.save();
+            let chats = AppState::chats(cx);
+            // Filed with the chat it came from so it shows under it
+            let folder = chats.read().library.filing(chat.uuid()).folder;
+            chats.write().library.file(*branch.uuid(), folder);
+            AppState::new_chat(cx, branch);
+            AppState::page(cx).set(Page::Chat);
+        }
+    }
+
+    /// Folds the chat `from` into `into` and takes it out of the sidebar
+    pub fn merge_chats(cx: &ScopeState, into: Chat, from: Uuid, order: MergeOrder) {
+        let chats = AppState::chats(cx);
+        let Some(from) = chats.read().get(&from).copied().filter(|from| *from != into) else {
+            return;
+        };
+        let ours = into.messages.read().msgs.clone();
+        let merged = merge_messages(ours, from.messages.read().msgs.clone(), order);
+        into.messages.write().msgs = merged;
+        into.added_personas.write().extend(from.added_personas.read().iter().copied());
+        into.save();
+        from.save();
+        chats.write().remove(from.uuid());
+    }
+
+    /// Speaks as the `n`th, from 1, persona of the active chat
+    pub fn select_persona(&self, n: u8) {
+        if let Some(chat) = *self.active_chat.read() {
+            let persona = chat.added_personas.read().get_index(usize::from(n).saturating_sub(1)).copied();
+            if let Some(persona) = persona {
+                chat.active_persona.set(persona);
+            }
+        }
+    }
+
+    /// Folds local changes into this device's sync document, merges in `remote`
+    /// if given and applies the result, returning the document to hand to other replicas
+    pub fn sync(&self, remote: Option<&SyncDoc>) -> SyncDoc {
+        let mut doc = SyncDoc::load();
+        doc.observe(&self.personas.read(), &self.chats.read().snapshot());
+        if let Some(remote) = remote {
+            doc.merge(remote);
+        }
+        doc.save();
+
+        self.personas.set(doc.personas());
+        self.chats.write().apply_synced(doc.chats());
+        self.active_chat.set(self.chats.read().active_chat().copied());
+        doc
+    }
+
     pub fn load(cx: &ScopeState) {
         let personas: Signal<Personas> =
-            use_synced_storage(cx, "ifs_personas".to_string(), || {
-                Personas::new(Persona {
-                    name: "Me".to_string(),
-                    colour: Rgb(0x49, 0x55, 0x65),
-                })
-            });
+            use_synced_storage(cx, "ifs_personas".to_string(), Personas::starter);
 
         let chats: Signal<Chats> =
             use_synced_storage(cx, "ifs_chats".to_string(), move || {
@@ -83,7 +196,13 @@ impl AppState {
 
         let active_chat = use_signal(cx, || chats.read().active_chat().copied());
 
-        let app_state = AppState { personas, chats, active_chat };
+        let settings: Signal<Settings> =
+            use_synced_storage(cx, "ifs_settings".to_string(), Settings::default);
+        let guides: Signal<Guides> = use_synced_storage(cx, "ifs_guides".to_string(), Guides::default);
+        let page = use_signal(cx, Page::default);
+        let renaming = use_signal(cx, || false);
+
+        let app_state = AppState { personas, chats, active_chat, settings, guides, page, renaming };
         use_context_provider(cx, || app_state);
     }
 }
@@ -2,10 +2,15 @@ use dioxus_signals::Signal;
 use indexmap::{indexmap, indexset, IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use std::cmp::Reverse;
 use std::hash::{Hash, Hasher};
 
+use super::{ChatSort, CheckIns, Dragged, DropTarget, EmotionTag, GuidedSession, Library, Personas, DEFAULT_DATE_FORMAT};
 use crate::storage;
 
+/// Sidebar chats by the folder they're in, each with how deep a branch it is
+pub type FolderChats = IndexMap<Option<Uuid>, Vec<(Chat, usize)>>;
+
 #[derive(Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct Chats {
     chat_ids: IndexSet<Uuid>,
@@ -13,6 +18,9 @@ pub struct Chats {
     chats: IndexSet<Chat>,
     active_chat: Option<Uuid>,
     save_toggle: bool,
+    /// Folders, tags and pins
+    #[serde(default)]
+    pub library: Library,
 }
 
 impl Chats {
@@ -22,6 +30,7 @@ impl Chats {
             chats: Default::default(),
             active_chat: None,
             save_toggle: false,
+            library: Library::default(),
         }
     }
 
@@ -74,13 +83,239 @@ impl Chats {
         self.active_chat = Some(uuid);
     }
 
-    pub fn delete_active(&mut self) {
-        if let Some(active_chat) = &self.active_chat {
-            self.chats.shift_remove(active_chat);
-            self.chat_ids.shift_remove(active_chat);
+    /// The chat after the active one in the sidebar as it's shown, or before it when going `backwards`
+    pub fn cycle_chat(&self, backwards: bool) -> Option<Uuid> {
+        let shown = self.shown();
+        let count = shown.len();
+        let index = match self.active_chat.and_then(|uuid| shown.iter().position(|shown| *shown == uuid)) {
+            Some(index) if backwards => (index + count - 1) % count,
+            Some(index) => (index + 1) % count,
+            None if backwards => count.checked_sub(1)?,
+            None => 0,
+        };
+        shown.get(index).copied()
+    }
+
+    /// Every chat the sidebar shows, top to bottom, leaving out those in collapsed folders
+    pub fn shown(&self) -> Vec<Uuid> {
+        let mut shown: Vec<Uuid> = self.pinned().iter().map(|chat| chat.uuid).collect();
+        self.push_shown(None, &self.by_folder(), &mut shown);
+        shown
+    }
+
+    fn push_shown(&self, folder: Option<Uuid>, by_folder: &FolderChats, shown: &mut Vec<Uuid>) {
+        let collapsed = folder.and_then(|folder| self.library.folders.get(&folder)).is_some_and(|folder| folder.collapsed);
+        if collapsed {
+            return;
+        }
+        let subfolders = self.library.subfolders(folder);
+        // At the top level the loose chats come after the folders, inside one they come after its subfolders
+        for subfolder in subfolders {
+            self.push_shown(Some(subfolder), by_folder, shown);
+        }
+        shown.extend(by_folder.get(&folder).into_iter().flatten().map(|(chat, _)| chat.uuid));
+    }
+
+    /// Whether the chat gets past the sidebar's filter
+    pub fn passes_filter(&self, chat: &Chat) -> bool {
+        let filter = &self.library.filter;
+        if filter.is_empty() {
+            return true;
+        }
+        let (started, last) = chat.activity();
+        filter.matches_name(&chat.name.read())
+            && filter.persona.map_or(true, |persona| chat.added_personas.read().contains(&persona))
+            && filter.tag.as_ref().map_or(true, |tag| self.library.has_tag(&chat.uuid, tag))
+            && filter.in_range(started, last)
+    }
+
+    fn sort(&self, chats: &mut [Chat]) {
+        match self.library.sort {
+            ChatSort::Manual => {}
+            ChatSort::LastActivity => chats.sort_by_key(|chat| Reverse(chat.activity().1)),
+            ChatSort::Created => chats.sort_by_key(|chat| Reverse(chat.activity().0)),
+            ChatSort::Name => chats.sort_by_cached_key(|chat| chat.name.read().to_lowercase()),
+            ChatSort::MessageCount => chats.sort_by_key(|chat| Reverse(chat.messages.read().msgs.len())),
+        }
+    }
+
+    pub fn get(&self, uuid: &Uuid) -> Option<&Chat> {
+        self.chats.get(uuid)
+    }
+
+    /// Takes a chat out of the sidebar, its saved messages stay where they are
+    pub fn remove(&mut self, uuid: &Uuid) {
+        self.chats.shift_remove(uuid);
+        self.chat_ids.shift_remove(uuid);
+        self.library.forget(uuid);
+        if self.active_chat == Some(*uuid) {
             self.active_chat = None;
         }
     }
+
+    pub fn delete_active(&mut self) {
+        if let Some(active_chat) = self.active_chat {
+            self.remove(&active_chat);
+        }
+    }
+
+    /// Puts the chat just before `before` in the sidebar
+    pub fn move_chat(&mut self, uuid: Uuid, before: Uuid) {
+        if uuid == before || !self.chat_ids.contains(&uuid) {
+            return;
+        }
+        let mut ids: Vec<Uuid> = self.chat_ids.iter().copied().filter(|id| *id != uuid).collect();
+        let at = ids.iter().position(|id| *id == before).unwrap_or(ids.len());
+        ids.insert(at, uuid);
+        self.chat_ids = ids.into_iter().collect();
+        let mut chats = std::mem::take(&mut self.chats);
+        self.chats = self.chat_ids.iter().filter_map(|id| chats.shift_remove(id)).collect();
+        // Chats that failed to load keep their place at the end
+        self.chats.extend(chats);
+    }
+
+    /// Files or reorders whatever was dragged where it was dropped
+    pub fn refile(&mut self, dragged: Dragged, target: DropTarget) {
+        match (dragged, target) {
+            (Dragged::Chat(chat), DropTarget::Pinned) => self.library.filing_mut(chat).pinned = true,
+            (Dragged::Chat(chat), DropTarget::Folder(folder)) => {
+                self.library.filing_mut(chat).pinned = false;
+                self.library.file(chat, folder);
+            }
+            (Dragged::Chat(chat), DropTarget::Chat(before)) => {
+                let target = self.library.filing(&before);
+                self.move_chat(chat, before);
+                self.library.filing_mut(chat).pinned = target.pinned;
+                self.library.file(chat, target.folder);
+            }
+            (Dragged::Folder(folder), DropTarget::Folder(parent)) => {
+                self.library.move_folder(folder, parent);
+            }
+            (Dragged::Folder(folder), DropTarget::Chat(chat)) => {
+                let parent = self.library.filing(&chat).folder;
+                self.library.move_folder(folder, parent);
+            }
+            (Dragged::Folder(_), DropTarget::Pinned) => {}
+        }
+    }
+
+    /// The chats shown directly in each folder, or outside any under None, with branches under
+    /// their chat. Pinned chats are left out as they have a group of their own, as are those
+    /// the filter doesn't let through.
+    pub fn by_folder(&self) -> FolderChats {
+        let mut by_folder = FolderChats::new();
+        for (chat, depth) in self.tree() {
+            let filing = self.library.filing(&chat.uuid);
+            if !filing.pinned && self.passes_filter(&chat) {
+                by_folder.entry(filing.folder).or_default().push((chat, depth));
+            }
+        }
+        by_folder
+    }
+
+    /// The chats shown directly in `folder`, see [`Chats::by_folder`] for the whole sidebar at once
+    pub fn filed_in(&self, folder: Option<Uuid>) -> Vec<(Chat, usize)> {
+        self.by_folder().swap_remove(&folder).unwrap_or_default()
+    }
+
+    pub fn pinned(&self) -> Vec<Chat> {
+        let mut pinned: Vec<Chat> = self
+            .chats
+            .iter()
+            .filter(|chat| self.library.filing(&chat.uuid).pinned && self.passes_filter(chat))
+            .copied()
+            .collect();
+        self.sort(&mut pinned);
+        pinned
+    }
+
+    /// An index for chats saved outside of the app, see [`Journal`](super::Journal)
+    pub fn from_ids(chat_ids: IndexSet<Uuid>, active_chat: Option<Uuid>) -> Self {
+        Chats {
+            chat_ids,
+            active_chat,
+            ..Default::default()
+        }
+    }
+
+    pub fn chat_ids(&self) -> &IndexSet<Uuid> {
+        &self.chat_ids
+    }
+
+    /// Chats in sidebar order with each branch right under the chat it came from, and how deep it is
+    pub fn tree(&self) -> Vec<(Chat, usize)> {
+        let mut tree = Vec::with_capacity(self.chats.len());
+        let mut roots: Vec<Chat> = self
+            .chats
+            .iter()
+            .filter(|chat| chat.branch.map_or(true, |branch| !self.chats.contains(&branch.chat)))
+            .copied()
+            .collect();
+        self.sort(&mut roots);
+        for root in roots {
+            self.push_branches(root, 0, &mut tree);
+        }
+        tree
+    }
+
+    fn push_branches(&self, chat: Chat, depth: usize, tree: &mut Vec<(Chat, usize)>) {
+        tree.push((chat, depth));
+        let mut branches: Vec<Chat> = self
+            .chats
+            .iter()
+            .filter(|other| other.branch.is_some_and(|branch| branch.chat == chat.uuid))
+            .copied()
+            .collect();
+        self.sort(&mut branches);
+        for branch in branches {
+            self.push_branches(branch, depth + 1, tree);
+        }
+    }
+
+    /// Plain copies of every loaded chat, in sidebar order
+    pub fn snapshot(&self) -> Vec<ChatData> {
+        self.chats.iter().map(ChatData::from).collect()
+    }
+
+    /// Replaces the loaded chats with the result of a sync, keeping each chat's
+    /// unsent message and active persona where possible, and saves every chat
+    pub fn apply_synced(&mut self, synced: Vec<ChatData>) {
+        let mut chats = IndexSet::with_capacity(synced.len());
+        for data in synced {
+            let chat = match self.chats.get(&data.uuid) {
+                Some(chat) => {
+                    let mut chat = *chat;
+                    chat.started = data.started;
+                    chat.name.set(data.name);
+                    chat.check_ins.set(data.check_ins);
+                    chat.messages.set(data.messages);
+                    if !data.added_personas.contains(&*chat.active_persona.read()) {
+                        chat.active_persona.set(data.active_persona);
+                    }
+                    chat.added_personas.set(data.added_personas);
+                    chat
+                }
+                None => Chat::from(data),
+            };
+            chat.save();
+            chats.insert(chat);
+        }
+        self.chat_ids = chats.iter().map(|chat| chat.uuid).collect();
+        if let Some(active_chat) = self.active_chat {
+            if !self.chat_ids.contains(&active_chat) {
+                self.active_chat = None;
+            }
+        }
+        self.chats = chats;
+    }
+}
+
+/// The persona after `active` in the chat, or before it when going `backwards`, wrapping around
+pub fn cycle_persona(added_personas: &IndexSet<Uuid>, active: &Uuid, backwards: bool) -> Uuid {
+    let count = added_personas.len();
+    let index = added_personas.get_index_of(active).unwrap_or(0);
+    let index = if backwards { (index + count - 1) % count } else { (index + 1) % count };
+    *added_personas.get_index(index).unwrap()
 }
 
 #[derive(Clone, Copy, Default, Serialize, Deserialize)]
@@ -91,18 +326,22 @@ pub struct Chat {
     pub active_persona: Signal<Uuid>,
     pub added_personas: Signal<IndexSet<Uuid>>,
     pub current_message: Signal<String>,
+    #[serde(default)]
+    pub branch: Option<Branch>,
+    /// When the chat was started in unix milliseconds, 0 for chats from before it was kept
+    #[serde(default)]
+    pub started: i64,
+    /// The guide being followed, if any
+    #[serde(default)]
+    pub session: Signal<Option<GuidedSession>>,
+    #[serde(default)]
+    pub check_ins: Signal<CheckIns>,
 }
 
 impl Chat {
     /// Creates a new chat with the specified Persona as starter
     pub fn new(persona_id: Uuid) -> Self {
-        Chat {
-            uuid: Uuid::new_v4(),
-            name: Signal::new(format!("{}", chrono::Utc::now().format("%a, %h %d, %Y"))),
-            active_persona: Signal::new(persona_id),
-            added_personas: Signal::new(indexset! { persona_id }),
-            ..Default::default()
-        }
+        Chat::from(ChatData::new(persona_id))
     }
 
     fn load_or(uuid: &Uuid, default_chat: Chat) -> Self {
@@ -116,19 +355,81 @@ impl Chat {
     }
 
     pub fn send(&self) {
-        self.messages.write().msgs.push(Message {
-            uuid: Uuid::new_v4(),
-            msg: self.current_message.read().clone(),
-            persona: *self.active_persona.read(),
-        });
+        self.send_as(MessageKind::Speech, None)
+    }
+
+    /// Sends the current message as the active persona, with the given kind and in reply to a message
+    pub fn send_as(&self, kind: MessageKind, reply_to: Option<Uuid>) {
+        let mut message = Message::new(kind, *self.active_persona.read(), self.current_message.read().clone());
+        message.reply_to = reply_to;
+        self.messages.write().msgs.push(message);
         self.current_message.set(String::new())
     }
 
+    /// When the chat was started and when it was last written in, in unix milliseconds.
+    /// Older chats count as started with their first message.
+    pub fn activity(&self) -> (i64, i64) {
+        let messages = self.messages.read();
+        let sent = messages.msgs.iter().map(|msg| msg.sent).filter(|sent| *sent > 0);
+        let first = sent.clone().min();
+        let started = if self.started > 0 { self.started } else { first.unwrap_or(0) };
+        (started, sent.max().unwrap_or(started).max(started))
+    }
+
+    /// Adds a message without touching what's being written
+    pub fn post(&self, kind: MessageKind, persona: Uuid, msg: String) {
+        self.messages.write().msgs.push(Message::new(kind, persona, msg));
+    }
+
+    /// Sends the current message, first switching to the persona it's addressed to with a
+    /// leading `@name` and adding them to the chat if needed. A bare mention only switches.
+    /// Returns whether anything was sent.
+    pub fn send_mentioned(&self, personas: &Personas, kind: MessageKind, reply_to: Option<Uuid>) -> bool {
+        let msg = self.current_message.read().clone();
+        if let Some((persona, rest)) = personas.parse_mention(&msg) {
+            self.add_persona(persona);
+            self.current_message.set(rest.to_string());
+        }
+        let sending = !self.current_message.read().is_empty();
+        if sending {
+            self.send_as(kind, reply_to);
+        }
+        sending
+    }
+
     pub fn add_persona(&self, uuid: Uuid) -> bool {
         self.active_persona.set(uuid);
         self.added_personas.write().insert(uuid)
     }
 
+    /// Copies of the messages with these ids, in chat order
+    pub fn selected_messages(&self, uuids: &IndexSet<Uuid>) -> Vec<Message> {
+        self.messages.read().msgs.iter().filter(|msg| uuids.contains(&msg.uuid)).cloned().collect()
+    }
+
+    /// Removes the messages with these ids, returning them in chat order
+    pub fn take_messages(&self, uuids: &IndexSet<Uuid>) -> Vec<Message> {
+        let taken = self.selected_messages(uuids);
+        self.messages.write().msgs.retain(|msg| !uuids.contains(&msg.uuid));
+        taken
+    }
+
+    /// Adds messages from another chat at the end, along with whoever speaks in them.
+    /// Replies to messages this chat doesn't have become plain messages.
+    pub fn receive(&self, msgs: Vec<Message>) {
+        let mut messages = self.messages.write();
+        let mut known: IndexSet<Uuid> = messages.msgs.iter().map(|msg| msg.uuid).collect();
+        let mut added_personas = self.added_personas.write();
+        for mut msg in msgs {
+            msg.reply_to = msg.reply_to.filter(|parent| known.contains(parent));
+            if msg.kind.has_speaker() {
+                added_personas.insert(msg.persona);
+            }
+            known.insert(msg.uuid);
+            messages.msgs.push(msg);
+        }
+    }
+
     pub fn uuid(&self) -> &Uuid {
         &self.uuid
     }
@@ -157,14 +458,318 @@ impl indexmap::Equivalent<Chat> for Uuid {
 unsafe impl Send for Chat {}
 unsafe impl Sync for Chat {}
 
-#[derive(Clone, PartialEq, Serialize, Deserialize)]
+/// A [`Chat`] without signals, serialized the same way so it can be read and
+/// written outside of the app
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatData {
+    pub uuid: Uuid,
+    pub name: String,
+    pub messages: Messages,
+    pub active_persona: Uuid,
+    pub added_personas: IndexSet<Uuid>,
+    pub current_message: String,
+    /// Where this chat was branched off another, if it was
+    #[serde(default)]
+    pub branch: Option<Branch>,
+    /// When the chat was started in unix milliseconds, 0 for chats from before it was kept
+    #[serde(default)]
+    pub started: i64,
+    /// The guide being followed, if any
+    #[serde(default)]
+    pub session: Option<GuidedSession>,
+    /// The 8 Cs rated before and after the session
+    #[serde(default)]
+    pub check_ins: CheckIns,
+}
+
+/// The chat a branch came from and the last message they share
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Branch {
+    pub chat: Uuid,
+    pub message: Uuid,
+}
+
+impl ChatData {
+    /// Creates a new chat with the specified Persona as starter
+    pub fn new(persona_id: Uuid) -> Self {
+        ChatData {
+            uuid: Uuid::new_v4(),
+            // Named by the local date, as Settings::chat_name does
+            name: format!("{}", chrono::Local::now().format(DEFAULT_DATE_FORMAT)),
+            active_persona: persona_id,
+            added_personas: indexset! { persona_id },
+            started: chrono::Utc::now().timestamp_millis(),
+            ..Default::default()
+        }
+    }
+
+    pub fn storage_key(uuid: &Uuid) -> String {
+        format!("ifs_chat_{}", uuid)
+    }
+
+    pub fn save(&self) {
+        storage::store(ChatData::storage_key(&self.uuid), self.clone());
+    }
+
+    pub fn send(&mut self, persona: Uuid, msg: String) {
+        self.post(MessageKind::Speech, persona, msg)
+    }
+
+    pub fn post(&mut self, kind: MessageKind, persona: Uuid, msg: String) {
+        self.messages.msgs.push(Message::new(kind, persona, msg));
+    }
+
+    pub fn add_persona(&mut self, uuid: Uuid) -> bool {
+        self.active_persona = uuid;
+        self.added_personas.insert(uuid)
+    }
+
+    /// A new chat with the messages up to and including `message`, or None if it isn't in this chat.
+    /// Its personas start out as this chat's but are its own from then on.
+    pub fn branch_from(&self, message: &Uuid) -> Option<ChatData> {
+        let end = self.messages.msgs.iter().position(|msg| msg.uuid == *message)?;
+        Some(ChatData {
+            uuid: Uuid::new_v4(),
+            name: format!("{} (branch)", self.name),
+            messages: Messages {
+                msgs: self.messages.msgs[..=end].to_vec(),
+            },
+            active_persona: self.active_persona,
+            added_personas: self.added_personas.clone(),
+            current_message: String::new(),
+            branch: Some(Branch {
+                chat: self.uuid,
+                message: *message,
+            }),
+            started: chrono::Utc::now().timestamp_millis(),
+            session: None,
+            check_ins: CheckIns::default(),
+        })
+    }
+}
+
+impl From<&Chat> for ChatData {
+    fn from(chat: &Chat) -> Self {
+        ChatData {
+            uuid: chat.uuid,
+            name: chat.name.read().clone(),
+            messages: chat.messages.read().clone(),
+            active_persona: *chat.active_persona.read(),
+            added_personas: chat.added_personas.read().clone(),
+            current_message: chat.current_message.read().clone(),
+            branch: chat.branch,
+            started: chat.started,
+            session: chat.session.read().clone(),
+            check_ins: *chat.check_ins.read(),
+        }
+    }
+}
+
+impl From<ChatData> for Chat {
+    fn from(data: ChatData) -> Self {
+        Chat {
+            uuid: data.uuid,
+            name: Signal::new(data.name),
+            messages: Signal::new(data.messages),
+            active_persona: Signal::new(data.active_persona),
+            added_personas: Signal::new(data.added_personas),
+            current_message: Signal::new(data.current_message),
+            branch: data.branch,
+            started: data.started,
+            session: Signal::new(data.session),
+            check_ins: Signal::new(data.check_ins),
+        }
+    }
+}
+
+/// How merging puts two chats' messages together
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeOrder {
+    /// By when they were sent
+    Interleave,
+    /// The other chat's after this one's
+    Append,
+}
+
+/// `theirs` folded into `ours`. Each side keeps its own order, messages from before send times
+/// were recorded stay with the one before them, and messages both share, like a branch's, appear once.
+pub fn merge_messages(ours: Vec<Message>, theirs: Vec<Message>, order: MergeOrder) -> Vec<Message> {
+    let shared: IndexSet<Uuid> = ours.iter().map(|msg| msg.uuid).collect();
+    let theirs: Vec<Message> = theirs.into_iter().filter(|msg| !shared.contains(&msg.uuid)).collect();
+    if order == MergeOrder::Append {
+        return ours.into_iter().chain(theirs).collect();
+    }
+    let times = |msgs: &[Message]| -> Vec<i64> {
+        let mut last = 0;
+        msgs.iter()
+            .map(|msg| {
+                last = last.max(msg.sent);
+                last
+            })
+            .collect()
+    };
+    let (our_times, their_times) = (times(&ours), times(&theirs));
+    let mut merged = Vec::with_capacity(ours.len() + theirs.len());
+    let (mut ours, mut theirs) = (ours.into_iter().zip(our_times).peekable(), theirs.into_iter().zip(their_times).peekable());
+    loop {
+        let take_theirs = match (ours.peek(), theirs.peek()) {
+            (Some((_, our_time)), Some((_, their_time))) => their_time < our_time,
+            (None, Some(_)) => true,
+            (_, None) => false,
+        };
+        let next = if take_theirs { theirs.next() } else { ours.next() };
+        match next {
+            Some((msg, _)) => merged.push(msg),
+            None => break,
+        }
+    }
+    merged
+}
+
+/// Copies of `msgs` with new ids, replies among them pointing at the copies
+pub fn copy_messages(msgs: &[Message]) -> Vec<Message> {
+    let ids: IndexMap<Uuid, Uuid> = msgs.iter().map(|msg| (msg.uuid, Uuid::new_v4())).collect();
+    msgs.iter()
+        .map(|msg| Message {
+            uuid: ids[&msg.uuid],
+            reply_to: msg.reply_to.map(|parent| ids.get(&parent).copied().unwrap_or(parent)),
+            ..msg.clone()
+        })
+        .collect()
+}
+
+/// What a message is, only speech and asides are said by their persona
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MessageKind {
+    #[default]
+    Speech,
+    /// Scene descriptions and facilitator notes
+    Narration,
+    /// A thought or body sensation, not said out loud
+    Aside,
+    /// Something the app did, like a timer running out
+    Event,
+}
+
+impl MessageKind {
+    pub const ALL: [MessageKind; 4] = [MessageKind::Speech, MessageKind::Narration, MessageKind::Aside, MessageKind::Event];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MessageKind::Speech => "Speech",
+            MessageKind::Narration => "Narration",
+            MessageKind::Aside => "Aside",
+            MessageKind::Event => "Event",
+        }
+    }
+
+    /// Whether the message is shown as coming from its persona
+    pub fn has_speaker(self) -> bool {
+        matches!(self, MessageKind::Speech | MessageKind::Aside)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Message {
     pub uuid: Uuid,
     pub msg: String,
+    /// Who said it, or for narration and events who was speaking when it was written
     pub persona: Uuid,
+    #[serde(default)]
+    pub kind: MessageKind,
+    /// The earlier message this answers, if any
+    #[serde(default)]
+    pub reply_to: Option<Uuid>,
+    /// Unix milliseconds, 0 for messages from before this was recorded
+    #[serde(default)]
+    pub sent: i64,
+    /// How it felt, see [`EMOTION_WHEEL`](super::EMOTION_WHEEL)
+    #[serde(default)]
+    pub emotions: Vec<EmotionTag>,
 }
 
-#[derive(Clone, Default, Serialize, Deserialize)]
+impl Message {
+    pub fn new(kind: MessageKind, persona: Uuid, msg: String) -> Self {
+        Message {
+            uuid: Uuid::new_v4(),
+            msg,
+            persona,
+            kind,
+            reply_to: None,
+            sent: chrono::Utc::now().timestamp_millis(),
+            emotions: Vec::new(),
+        }
+    }
+
+    /// The start of the message on one line, for quoting it
+    pub fn preview(&self, chars: usize) -> String {
+        let line = self.msg.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.chars().count() > chars {
+            format!("{}…", line.chars().take(chars).collect::<String>().trim_end())
+        } else {
+            line
+        }
+    }
+
+    /// Who said it as shown in search results and transcripts, `Critic (aside)` or just `Narration`
+    pub fn speaker(&self, personas: &Personas) -> String {
+        let name = personas.get(&self.persona).map_or("Unknown", |persona| persona.name.as_str());
+        match self.kind {
+            MessageKind::Speech => name.to_string(),
+            MessageKind::Aside => format!("{name} (aside)"),
+            MessageKind::Narration | MessageKind::Event => self.kind.name().to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Messages {
     pub msgs: Vec<Message>,
 }
+
+impl Messages {
+    pub fn get(&self, uuid: &Uuid) -> Option<&Message> {
+        self.msgs.iter().find(|msg| msg.uuid == *uuid)
+    }
+
+    pub fn get_mut(&mut self, uuid: &Uuid) -> Option<&mut Message> {
+        self.msgs.iter_mut().find(|msg| msg.uuid == *uuid)
+    }
+
+    /// The line of dialogue `uuid` is part of: the messages it replies to back to the first,
+    /// then itself and every reply under it, in chat order
+    pub fn thread(&self, uuid: &Uuid) -> IndexSet<Uuid> {
+        let mut thread = IndexSet::new();
+        let mut current = self.get(uuid);
+        while let Some(msg) = current {
+            // A reply loop from a bad merge shouldn't hang us
+            if !thread.insert(msg.uuid) {
+                break;
+            }
+            current = msg.reply_to.and_then(|parent| self.get(&parent));
+        }
+        let mut under = indexset! { *uuid };
+        loop {
+            let before = under.len();
+            for msg in self.msgs.iter() {
+                if msg.reply_to.is_some_and(|parent| under.contains(&parent)) {
+                    under.insert(msg.uuid);
+                }
+            }
+            if under.len() == before {
+                break;
+            }
+        }
+        thread.extend(under);
+        self.msgs
+            .iter()
+            .map(|msg| msg.uuid)
+            .filter(|uuid| thread.contains(uuid))
+            .collect()
+    }
+
+    /// How many messages answer `uuid` directly
+    pub fn reply_count(&self, uuid: &Uuid) -> usize {
+        self.msgs.iter().filter(|msg| msg.reply_to == Some(*uuid)).count()
+    }
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+
+/// The 8 Cs of Self energy
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Quality {
+    Calm,
+    Curiosity,
+    Compassion,
+    Confidence,
+    Courage,
+    Clarity,
+    Creativity,
+    Connectedness,
+}
+
+impl Quality {
+    pub const ALL: [Quality; 8] = [
+        Quality::Calm,
+        Quality::Curiosity,
+        Quality::Compassion,
+        Quality::Confidence,
+        Quality::Courage,
+        Quality::Clarity,
+        Quality::Creativity,
+        Quality::Connectedness,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Quality::Calm => "Calm",
+            Quality::Curiosity => "Curiosity",
+            Quality::Compassion => "Compassion",
+            Quality::Confidence => "Confidence",
+            Quality::Courage => "Courage",
+            Quality::Clarity => "Clarity",
+            Quality::Creativity => "Creativity",
+            Quality::Connectedness => "Connectedness",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+pub const RATINGS: std::ops::RangeInclusive<u8> = 1..=10;
+
+/// How much of each C there was at one moment, rated 1 to 10
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CheckIn {
+    /// When it was taken, in unix milliseconds
+    pub taken: i64,
+    /// In the order of [`Quality::ALL`]
+    pub ratings: [u8; 8],
+}
+
+impl Default for CheckIn {
+    fn default() -> Self {
+        CheckIn {
+            taken: 0,
+            ratings: [5; 8],
+        }
+    }
+}
+
+impl CheckIn {
+    /// A check-in taken now with every C in the middle
+    pub fn now() -> Self {
+        CheckIn {
+            taken: chrono::Utc::now().timestamp_millis(),
+            ..Default::default()
+        }
+    }
+
+    pub fn rating(&self, quality: Quality) -> u8 {
+        self.ratings[quality.index()]
+    }
+
+    pub fn rate(&mut self, quality: Quality, rating: u8) {
+        self.ratings[quality.index()] = rating.clamp(*RATINGS.start(), *RATINGS.end());
+    }
+
+    /// Every C with its rating, like `Calm 6, Curiosity 7`
+    pub fn summary(&self) -> String {
+        let ratings: Vec<String> =
+            Quality::ALL.iter().map(|quality| format!("{} {}", quality.name(), self.rating(*quality))).collect();
+        ratings.join(", ")
+    }
+
+    pub fn average(&self) -> f64 {
+        self.ratings.iter().map(|rating| *rating as f64).sum::<f64>() / self.ratings.len() as f64
+    }
+}
+
+/// Whether a check-in comes before or after the session
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Moment {
+    Start,
+    #[default]
+    End,
+}
+
+impl Moment {
+    pub const ALL: [Moment; 2] = [Moment::Start, Moment::End];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Moment::Start => "Start",
+            Moment::End => "End",
+        }
+    }
+}
+
+/// A chat's optional check-ins, saved with it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CheckIns {
+    pub start: Option<CheckIn>,
+    pub end: Option<CheckIn>,
+}
+
+impl CheckIns {
+    pub fn get(&self, moment: Moment) -> Option<&CheckIn> {
+        match moment {
+            Moment::Start => self.start.as_ref(),
+            Moment::End => self.end.as_ref(),
+        }
+    }
+
+    pub fn set(&mut self, moment: Moment, check_in: Option<CheckIn>) {
+        match moment {
+            Moment::Start => self.start = check_in,
+            Moment::End => self.end = check_in,
+        }
+    }
+
+    /// How much a C moved over the session, when both check-ins were taken
+    pub fn change(&self, quality: Quality) -> Option<i16> {
+        Some(self.end?.rating(quality) as i16 - self.start?.rating(quality) as i16)
+    }
+}
+
+/// One point of a trend, a session's check-in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrendPoint {
+    pub taken: i64,
+    pub ratings: [u8; 8],
+}
+
+/// The check-ins at `moment` taken since `since`, oldest first
+pub fn trend<'a>(check_ins: impl IntoIterator<Item = &'a CheckIns>, moment: Moment, since: i64) -> Vec<TrendPoint> {
+    let mut points: Vec<TrendPoint> = check_ins
+        .into_iter()
+        .filter_map(|check_ins| check_ins.get(moment))
+        .filter(|check_in| check_in.taken >= since)
+        .map(|check_in| TrendPoint {
+            taken: check_in.taken,
+            ratings: check_in.ratings,
+        })
+        .collect();
+    points.sort_by_key(|point| point.taken);
+    points
+}
+
+/// The average rating of each C over the points, None when there are none
+pub fn averages(points: &[TrendPoint]) -> Option<[f64; 8]> {
+    if points.is_empty() {
+        return None;
+    }
+    let mut sums = [0.0; 8];
+    for point in points {
+        for (sum, rating) in sums.iter_mut().zip(point.ratings) {
+            *sum += rating as f64;
+        }
+    }
+    Some(sums.map(|sum| sum / points.len() as f64))
+}
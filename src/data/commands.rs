@@ -0,0 +1,101 @@
+use super::Action;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// How much a use counts for compared to a good match, higher favours habits over typing
+const USAGE_WEIGHT: f64 = 20.0;
+/// Hours after which a use counts half as much
+const USAGE_HALF_LIFE: f64 = 72.0;
+
+/// Something the command palette can do
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    OpenChat(Uuid),
+    /// Speak as the persona, adding it to the chat first if needed
+    Persona(Uuid),
+    Run(Action),
+}
+
+impl Command {
+    /// Identifies the command across sessions for [`CommandUsage`]
+    pub fn key(&self) -> String {
+        match self {
+            Command::OpenChat(uuid) => format!("chat:{uuid}"),
+            Command::Persona(uuid) => format!("persona:{uuid}"),
+            Command::Run(action) => format!("action:{action:?}"),
+        }
+    }
+}
+
+/// How well `query` matches `text` as a subsequence, ignoring case, or None if it doesn't.
+/// Runs of characters and matches at the start of words score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = position + text[position..].iter().position(|c| *c == wanted)?;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 8;
+        }
+        score -= (found - position) as i64 / 4;
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    pub count: u32,
+    /// Unix seconds
+    pub last_used: i64,
+}
+
+/// How often and how recently each palette command was used, saved under `ifs_command_usage`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CommandUsage(pub HashMap<String, Usage>);
+
+impl CommandUsage {
+    pub fn load() -> Self {
+        storage::retrieve("ifs_command_usage", CommandUsage::default)
+    }
+
+    pub fn save(&self) {
+        storage::store("ifs_command_usage", self.clone());
+    }
+
+    pub fn record(&mut self, command: &Command, now: i64) {
+        let usage = self.0.entry(command.key()).or_default();
+        usage.count += 1;
+        usage.last_used = now;
+    }
+
+    /// Uses so far, each fading with age
+    pub fn frecency(&self, command: &Command, now: i64) -> f64 {
+        self.0.get(&command.key()).map_or(0.0, |usage| {
+            let hours = (now - usage.last_used).max(0) as f64 / 3600.0;
+            f64::from(usage.count) * 0.5f64.powf(hours / USAGE_HALF_LIFE)
+        })
+    }
+
+    /// `commands`, with their labels, that match `query`, best first. Ties keep their given order.
+    pub fn rank(&self, query: &str, commands: Vec<(Command, String)>, now: i64) -> Vec<(Command, String)> {
+        let mut scored: Vec<(f64, (Command, String))> = commands
+            .into_iter()
+            .filter_map(|(command, label)| {
+                let score = fuzzy_score(query, &label)? as f64 + USAGE_WEIGHT * self.frecency(&command, now);
+                Some((score, (command, label)))
+            })
+            .collect();
+        scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        scored.into_iter().map(|(_, item)| item).collect()
+    }
+}
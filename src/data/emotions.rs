@@ -0,0 +1,103 @@
+use super::Message;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The feelings wheel, each core emotion with the finer ones around it
+pub const EMOTION_WHEEL: [(&str, &[&str]); 7] = [
+    ("Joy", &["Content", "Proud", "Hopeful", "Playful", "Grateful", "Relieved"]),
+    ("Sadness", &["Lonely", "Hurt", "Grief", "Disappointed", "Despair", "Empty"]),
+    ("Anger", &["Frustrated", "Resentful", "Irritated", "Betrayed", "Jealous", "Bitter"]),
+    ("Fear", &["Anxious", "Scared", "Insecure", "Overwhelmed", "Helpless", "Worried"]),
+    ("Shame", &["Guilty", "Embarrassed", "Inadequate", "Exposed", "Humiliated", "Unworthy"]),
+    ("Disgust", &["Contempt", "Revulsion", "Disapproving", "Averse"]),
+    ("Surprise", &["Confused", "Amazed", "Startled", "Moved"]),
+];
+
+/// What custom emotions are grouped under
+pub const CUSTOM_FAMILY: &str = "Your own";
+
+pub const INTENSITIES: std::ops::RangeInclusive<u8> = 1..=5;
+
+/// A feeling in a message and how strongly it's there, from 1 to 5
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EmotionTag {
+    pub name: String,
+    pub intensity: u8,
+}
+
+/// The core emotion `name` is or belongs to on the wheel, ignoring case
+pub fn core_emotion(name: &str) -> Option<&'static str> {
+    EMOTION_WHEEL
+        .iter()
+        .find(|(core, ring)| core.eq_ignore_ascii_case(name) || ring.iter().any(|emotion| emotion.eq_ignore_ascii_case(name)))
+        .map(|(core, _)| *core)
+}
+
+/// The wheel's spelling of `name`, or `name` trimmed if it's a custom one
+fn canonical(name: &str) -> String {
+    let name = name.trim();
+    EMOTION_WHEEL
+        .iter()
+        .flat_map(|(core, ring)| std::iter::once(core).chain(ring.iter()))
+        .find(|emotion| emotion.eq_ignore_ascii_case(name))
+        .map_or_else(|| name.to_string(), |emotion| emotion.to_string())
+}
+
+impl Message {
+    /// Tags the message with an emotion, changing the intensity if it's already tagged with it
+    pub fn tag_emotion(&mut self, name: &str, intensity: u8) {
+        let name = canonical(name);
+        if name.is_empty() {
+            return;
+        }
+        let intensity = intensity.clamp(*INTENSITIES.start(), *INTENSITIES.end());
+        match self.emotions.iter_mut().find(|tag| tag.name.eq_ignore_ascii_case(&name)) {
+            Some(tag) => tag.intensity = intensity,
+            None => self.emotions.push(EmotionTag { name, intensity }),
+        }
+    }
+
+    pub fn untag_emotion(&mut self, name: &str) {
+        self.emotions.retain(|tag| !tag.name.eq_ignore_ascii_case(name));
+    }
+
+    /// Whether the text or an emotion it's tagged with contains `needle`, which has to be lowercase
+    pub fn matches(&self, needle: &str) -> bool {
+        self.msg.to_lowercase().contains(needle)
+            || self.emotions.iter().any(|tag| tag.name.to_lowercase().contains(needle))
+    }
+}
+
+/// How often a persona has felt an emotion and how strongly
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmotionSummary {
+    pub name: String,
+    /// The core emotion it belongs to, or [`CUSTOM_FAMILY`]
+    pub family: &'static str,
+    pub count: usize,
+    pub average_intensity: f64,
+}
+
+/// The emotions tagged on `persona`'s messages, most frequent first
+pub fn emotion_summary<'a>(messages: impl IntoIterator<Item = &'a Message>, persona: Uuid) -> Vec<EmotionSummary> {
+    let mut totals: IndexMap<String, (usize, u32)> = IndexMap::new();
+    for msg in messages.into_iter().filter(|msg| msg.persona == persona && msg.kind.has_speaker()) {
+        for tag in msg.emotions.iter() {
+            let total = totals.entry(tag.name.clone()).or_default();
+            total.0 += 1;
+            total.1 += tag.intensity as u32;
+        }
+    }
+    let mut summary: Vec<EmotionSummary> = totals
+        .into_iter()
+        .map(|(name, (count, intensity))| EmotionSummary {
+            family: core_emotion(&name).unwrap_or(CUSTOM_FAMILY),
+            average_intensity: intensity as f64 / count as f64,
+            name,
+            count,
+        })
+        .collect();
+    summary.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    summary
+}
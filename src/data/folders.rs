@@ -0,0 +1,254 @@
+use chrono::{NaiveDate, TimeZone};
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Folder {
+    pub name: String,
+    /// The folder this one is in, None at the top
+    pub parent: Option<Uuid>,
+    /// Whether its chats are hidden in the sidebar
+    #[serde(default)]
+    pub collapsed: bool,
+}
+
+/// Where a chat is filed and how it's labelled
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Filing {
+    pub folder: Option<Uuid>,
+    pub tags: IndexSet<String>,
+    /// Pinned chats sit at the top of the sidebar whatever folder they're in
+    pub pinned: bool,
+}
+
+/// How the sidebar is organised, saved with the [`Chats`](super::Chats)
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Library {
+    pub folders: IndexMap<Uuid, Folder>,
+    /// Chats that have never been filed, tagged or pinned have no entry
+    pub filing: IndexMap<Uuid, Filing>,
+    #[serde(default)]
+    pub filter: ChatFilter,
+    #[serde(default)]
+    pub sort: ChatSort,
+}
+
+/// Narrows the chats shown in the sidebar, empty parts let everything through
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatFilter {
+    /// Part of the chat's name, in any case
+    pub text: String,
+    /// A persona added to the chat
+    pub persona: Option<Uuid>,
+    pub tag: Option<String>,
+    /// The first day, as `YYYY-MM-DD`, the chat has to have been active by
+    pub from: Option<String>,
+    /// The last day, as `YYYY-MM-DD`, the chat has to have been started by
+    pub to: Option<String>,
+}
+
+impl ChatFilter {
+    pub fn is_empty(&self) -> bool {
+        *self == ChatFilter::default()
+    }
+
+    pub fn matches_name(&self, name: &str) -> bool {
+        name.to_lowercase().contains(&self.text.trim().to_lowercase())
+    }
+
+    /// Whether a chat started at `started` and last active at `last`, both unix milliseconds,
+    /// overlaps the date range. Days are local ones.
+    pub fn in_range(&self, started: i64, last: i64) -> bool {
+        let from = self.from.as_deref().and_then(parse_day);
+        let to = self.to.as_deref().and_then(parse_day);
+        from.map_or(true, |from| local_day(last) >= from) && to.map_or(true, |to| local_day(started) <= to)
+    }
+}
+
+/// A day as date inputs give it, `YYYY-MM-DD`
+pub fn parse_day(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()
+}
+
+fn local_day(millis: i64) -> NaiveDate {
+    chrono::Local
+        .timestamp_millis_opt(millis)
+        .earliest()
+        .map_or(NaiveDate::MIN, |time| time.date_naive())
+}
+
+/// The order chats are listed in within each sidebar group
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChatSort {
+    /// As they were made or dragged
+    #[default]
+    Manual,
+    /// Most recently written in first
+    LastActivity,
+    /// Newest first
+    Created,
+    Name,
+    /// Longest first
+    MessageCount,
+}
+
+impl ChatSort {
+    pub const ALL: [ChatSort; 5] = [
+        ChatSort::Manual,
+        ChatSort::LastActivity,
+        ChatSort::Created,
+        ChatSort::Name,
+        ChatSort::MessageCount,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ChatSort::Manual => "Your order",
+            ChatSort::LastActivity => "Last activity",
+            ChatSort::Created => "Newest",
+            ChatSort::Name => "Name",
+            ChatSort::MessageCount => "Most messages",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ChatSort> {
+        ChatSort::ALL.into_iter().find(|sort| sort.name() == name)
+    }
+}
+
+/// Tags typed as a comma separated list, without blanks or repeats that only differ in case
+pub fn parse_tags(text: &str) -> IndexSet<String> {
+    let mut seen = IndexSet::new();
+    text.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty() && seen.insert(tag.to_lowercase()))
+        .map(str::to_string)
+        .collect()
+}
+
+impl Library {
+    pub fn filing(&self, chat: &Uuid) -> Filing {
+        self.filing.get(chat).cloned().unwrap_or_default()
+    }
+
+    pub fn filing_mut(&mut self, chat: Uuid) -> &mut Filing {
+        self.filing.entry(chat).or_default()
+    }
+
+    /// Files the chat in `folder`, or at the top with None
+    pub fn file(&mut self, chat: Uuid, folder: Option<Uuid>) {
+        let folder = folder.filter(|folder| self.folders.contains_key(folder));
+        self.filing_mut(chat).folder = folder;
+    }
+
+    pub fn forget(&mut self, chat: &Uuid) {
+        self.filing.shift_remove(chat);
+    }
+
+    pub fn new_folder(&mut self, name: String, parent: Option<Uuid>) -> Uuid {
+        let uuid = Uuid::new_v4();
+        self.folders.insert(uuid, Folder { name, parent, collapsed: false });
+        uuid
+    }
+
+    /// Removes the folder, whatever was in it moves up to where the folder was
+    pub fn delete_folder(&mut self, uuid: &Uuid) {
+        let Some(folder) = self.folders.shift_remove(uuid) else {
+            return;
+        };
+        for other in self.folders.values_mut().filter(|other| other.parent == Some(*uuid)) {
+            other.parent = folder.parent;
+        }
+        for filing in self.filing.values_mut().filter(|filing| filing.folder == Some(*uuid)) {
+            filing.folder = folder.parent;
+        }
+    }
+
+    /// Whether `folder` is `ancestor` or somewhere inside it
+    pub fn is_within(&self, folder: Uuid, ancestor: Uuid) -> bool {
+        let mut current = Some(folder);
+        let mut seen = IndexSet::new();
+        while let Some(uuid) = current {
+            if uuid == ancestor {
+                return true;
+            }
+            if !seen.insert(uuid) {
+                return false;
+            }
+            current = self.folders.get(&uuid).and_then(|folder| folder.parent);
+        }
+        false
+    }
+
+    /// Moves a folder into another, refusing to put it inside itself
+    pub fn move_folder(&mut self, uuid: Uuid, parent: Option<Uuid>) -> bool {
+        if parent.is_some_and(|parent| self.is_within(parent, uuid)) || !self.folders.contains_key(&uuid) {
+            return false;
+        }
+        self.folders[&uuid].parent = parent;
+        true
+    }
+
+    /// The folders directly inside `parent`, or at the top with None
+    pub fn subfolders(&self, parent: Option<Uuid>) -> Vec<Uuid> {
+        self.folders
+            .iter()
+            .filter(|(_, folder)| folder.parent == parent)
+            .map(|(uuid, _)| *uuid)
+            .collect()
+    }
+
+    /// The folder's name with those it's in, like `Work / Conflicts`
+    pub fn path(&self, uuid: &Uuid) -> String {
+        let mut names = Vec::new();
+        let mut current = Some(*uuid);
+        while let Some(folder) = current.and_then(|uuid| self.folders.get(&uuid)) {
+            names.push(folder.name.as_str());
+            // A parent loop from a bad edit shouldn't hang us
+            if names.len() > self.folders.len() {
+                break;
+            }
+            current = folder.parent;
+        }
+        names.reverse();
+        names.join(" / ")
+    }
+
+    /// Every tag in use, alphabetically
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .filing
+            .values()
+            .flat_map(|filing| filing.tags.iter().cloned())
+            .collect::<IndexSet<String>>()
+            .into_iter()
+            .collect();
+        tags.sort_by_key(|tag| tag.to_lowercase());
+        tags
+    }
+
+    pub fn has_tag(&self, chat: &Uuid, tag: &str) -> bool {
+        self.filing
+            .get(chat)
+            .is_some_and(|filing| filing.tags.iter().any(|own| own.eq_ignore_ascii_case(tag)))
+    }
+}
+
+/// Something picked up in the sidebar
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dragged {
+    Chat(Uuid),
+    Folder(Uuid),
+}
+
+/// Where something can be dropped in the sidebar
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DropTarget {
+    Pinned,
+    /// Into a folder, or out of every folder with None
+    Folder(Option<Uuid>),
+    /// Just before the chat, in its folder
+    Chat(Uuid),
+}
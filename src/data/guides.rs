@@ -0,0 +1,176 @@
+use super::{Chat, MessageKind, Personas};
+use crate::storage;
+use serde::{Deserialize, Serialize};
+
+/// One step of a [`Guide`]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GuideStep {
+    pub title: String,
+    /// Posted as narration when the step starts
+    pub prompt: String,
+    /// Who should answer. If it's a persona's name the chat switches to them,
+    /// otherwise it's shown as a hint, like "The part"
+    #[serde(default)]
+    pub answer_as: String,
+}
+
+/// A script for a guided session, written as JSON so anyone can share their own
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Guide {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub steps: Vec<GuideStep>,
+}
+
+fn step(title: &str, prompt: &str, answer_as: &str) -> GuideStep {
+    GuideStep {
+        title: title.to_string(),
+        prompt: prompt.to_string(),
+        answer_as: answer_as.to_string(),
+    }
+}
+
+impl Guide {
+    /// The IFS "6 F's" for getting to know a protector
+    pub fn six_fs() -> Self {
+        Guide {
+            name: "The 6 F's".to_string(),
+            description: "Getting to know a protector, from finding it to hearing what it fears".to_string(),
+            steps: vec![
+                step("Find", "Which part wants your attention right now? Notice where you sense it, in your body or around it.", "Me"),
+                step("Focus", "Turn your attention to that part. Let the others know you're spending time with it.", "Me"),
+                step("Flesh out", "What does it look like, sound like or feel like? What would it like you to know?", "The part"),
+                step("Feel toward", "How do you feel toward this part? If it's anything other than curiosity or calm, ask that part to step back.", "Me"),
+                step("Befriend", "Ask how it came to have this job, and how long it's been doing it. Thank it.", "The part"),
+                step("Fears", "What is it afraid would happen if it stopped doing its job?", "The part"),
+            ],
+        }
+    }
+
+    /// One guide or a list of them, as written in a script file
+    pub fn parse(json: &str) -> Result<Vec<Guide>, String> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Script {
+            One(Guide),
+            Many(Vec<Guide>),
+        }
+        let guides = match serde_json::from_str(json).map_err(|e| format!("That isn't a guide script: {e}"))? {
+            Script::One(guide) => vec![guide],
+            Script::Many(guides) => guides,
+        };
+        if let Some(guide) = guides.iter().find(|guide| guide.name.trim().is_empty() || guide.steps.is_empty()) {
+            return Err(format!("Every guide needs a name and at least one step, {:?} doesn't have both", guide.name));
+        }
+        Ok(guides)
+    }
+}
+
+/// Guides imported by the user, saved under `ifs_guides`. The built in ones aren't stored.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Guides(pub Vec<Guide>);
+
+impl Guides {
+    pub fn load() -> Self {
+        storage::retrieve("ifs_guides", Guides::default)
+    }
+
+    pub fn save(&self) {
+        storage::store("ifs_guides", self.clone());
+    }
+
+    pub fn builtin() -> Vec<Guide> {
+        vec![Guide::six_fs()]
+    }
+
+    /// The built in guides followed by the imported ones
+    pub fn all(&self) -> Vec<Guide> {
+        let mut all = Guides::builtin();
+        all.extend(self.0.iter().cloned());
+        all
+    }
+
+    pub fn find(&self, name: &str) -> Option<Guide> {
+        self.all().into_iter().find(|guide| guide.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Adds the guides, replacing imported ones with the same name
+    pub fn import(&mut self, guides: Vec<Guide>) {
+        for guide in guides {
+            match self.0.iter_mut().find(|existing| existing.name.eq_ignore_ascii_case(&guide.name)) {
+                Some(existing) => *existing = guide,
+                None => self.0.push(guide),
+            }
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.0.retain(|guide| guide.name != name);
+    }
+}
+
+/// A chat's way through a guide. The guide is copied in so changing or removing it
+/// doesn't disturb a session already underway.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GuidedSession {
+    pub guide: Guide,
+    pub step: usize,
+}
+
+impl GuidedSession {
+    pub fn current(&self) -> Option<&GuideStep> {
+        self.guide.steps.get(self.step)
+    }
+
+    pub fn is_last_step(&self) -> bool {
+        self.step + 1 >= self.guide.steps.len()
+    }
+}
+
+impl Chat {
+    /// Starts walking through `guide` from its first step
+    pub fn start_guide(&self, guide: Guide, personas: &Personas) {
+        self.session.set(Some(GuidedSession { guide, step: 0 }));
+        self.post_guide_step(personas);
+    }
+
+    /// Moves on to the guide's next step, or ends the session after the last one.
+    /// Returns whether there was a next step.
+    pub fn next_guide_step(&self, personas: &Personas) -> bool {
+        let more = match self.session.write().as_mut() {
+            Some(session) if !session.is_last_step() => {
+                session.step += 1;
+                true
+            }
+            _ => false,
+        };
+        if more {
+            self.post_guide_step(personas);
+        } else {
+            self.end_guide();
+        }
+        more
+    }
+
+    pub fn end_guide(&self) {
+        let name = self.session.read().as_ref().map(|session| session.guide.name.clone());
+        if let Some(name) = name {
+            self.session.set(None);
+            self.post(MessageKind::Event, *self.active_persona.read(), format!("End of {name}"));
+            self.save();
+        }
+    }
+
+    /// Posts the current step's prompt and switches to the persona it suggests, if there is one
+    fn post_guide_step(&self, personas: &Personas) {
+        let Some(step) = self.session.read().as_ref().and_then(|session| session.current().cloned()) else {
+            return;
+        };
+        self.post(MessageKind::Narration, *self.active_persona.read(), format!("{}: {}", step.title, step.prompt));
+        if let Some(uuid) = personas.find_by_name(&step.answer_as) {
+            self.add_persona(uuid);
+        }
+        self.save();
+    }
+}
@@ -0,0 +1,190 @@
+use super::{ChatData, Chats, Library, MessageKind, Moment, Persona, Personas, Settings};
+use crate::colours::{palette, Rgb};
+use crate::storage;
+use indexmap::IndexSet;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Everything needed to move a journal to another install
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct JournalExport {
+    pub personas: Personas,
+    pub chats: Vec<ChatData>,
+}
+
+/// The personas and chats as saved by the app, loaded without any signals so
+/// the terminal front ends can read and write the same storage
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Journal {
+    pub personas: Personas,
+    pub chats: Vec<ChatData>,
+    pub active_chat: Option<Uuid>,
+    pub settings: Settings,
+    /// Kept as the app left it so saving the index doesn't lose it
+    pub library: Library,
+}
+
+impl Journal {
+    pub fn load() -> Self {
+        let personas: Personas = storage::retrieve("ifs_personas", Personas::starter);
+        let index: Chats = storage::retrieve("ifs_chats", Chats::default);
+        let chats = index
+            .chat_ids()
+            .iter()
+            .filter_map(|uuid| storage::try_retrieve(ChatData::storage_key(uuid)))
+            .collect();
+
+        Journal {
+            personas,
+            chats,
+            active_chat: *index.active_chat_uuid(),
+            settings: Settings::load(),
+            library: index.library,
+        }
+    }
+
+    pub fn save_personas(&self) {
+        storage::store("ifs_personas", self.personas.clone());
+    }
+
+    /// Saves the list of chats, each chat's messages are saved with [`ChatData::save`]
+    pub fn save_index(&self) {
+        let chat_ids: IndexSet<Uuid> = self.chats.iter().map(|chat| chat.uuid).collect();
+        let mut index = Chats::from_ids(chat_ids, self.active_chat);
+        index.library = self.library.clone();
+        storage::store("ifs_chats", index);
+    }
+
+    pub fn save(&self) {
+        self.save_personas();
+        self.chats.iter().for_each(ChatData::save);
+        self.save_index();
+    }
+
+    pub fn chat(&self, uuid: &Uuid) -> Option<&ChatData> {
+        self.chats.iter().find(|chat| chat.uuid == *uuid)
+    }
+
+    pub fn chat_mut(&mut self, uuid: &Uuid) -> Option<&mut ChatData> {
+        self.chats.iter_mut().find(|chat| chat.uuid == *uuid)
+    }
+
+    /// Finds a chat by its name, or failing that by the start of its uuid
+    pub fn find_chat(&self, name_or_id: &str) -> Option<&ChatData> {
+        if name_or_id.is_empty() {
+            return None;
+        }
+        self.chats
+            .iter()
+            .find(|chat| chat.name == name_or_id)
+            .or_else(|| {
+                self.chats
+                    .iter()
+                    .find(|chat| chat.uuid.to_string().starts_with(name_or_id))
+            })
+    }
+
+    /// Finds a persona by name, ignoring case
+    pub fn find_persona(&self, name: &str) -> Option<Uuid> {
+        self.personas.find_by_name(name)
+    }
+
+    /// Starts a chat as the settings ask and makes it the active one, saving it
+    pub fn new_chat(&mut self) -> Uuid {
+        let chat = self.settings.new_chat(&self.personas);
+        self.start_chat(chat)
+    }
+
+    /// Adds a chat, saves it and makes it the active one
+    pub fn start_chat(&mut self, chat: ChatData) -> Uuid {
+        let uuid = chat.uuid;
+        chat.save();
+        self.chats.push(chat);
+        self.active_chat = Some(uuid);
+        self.save_index();
+        uuid
+    }
+
+    /// Creates a persona, picking a distinct colour if none is given
+    pub fn new_persona(&mut self, name: String, colour: Option<Rgb>) -> Uuid {
+        let uuid = match colour {
+            Some(colour) => self.personas.push(Persona { name, colour }),
+            None => self.personas.push_named(name),
+        };
+        self.save_personas();
+        uuid
+    }
+
+    pub fn delete_chat(&mut self, uuid: &Uuid) {
+        self.chats.retain(|chat| chat.uuid != *uuid);
+        self.library.forget(uuid);
+        if self.active_chat == Some(*uuid) {
+            self.active_chat = None;
+        }
+        self.save_index();
+    }
+
+    pub fn export(&self) -> JournalExport {
+        JournalExport {
+            personas: self.personas.clone(),
+            chats: self.chats.clone(),
+        }
+    }
+
+    /// Adds the personas and chats this journal doesn't have yet, returning how many chats were added
+    pub fn import(&mut self, export: JournalExport) -> usize {
+        for (uuid, persona) in export.personas.iter() {
+            if self.personas.get(uuid).is_none() {
+                let mut persona = persona.clone();
+                // Black is what the picker left when no colour was chosen, and a colour close to
+                // another persona's would read as the same speaker, so give those a distinct one
+                if persona.colour == Rgb::default() || self.personas.colour_clashes(persona.colour) {
+                    persona.colour = self.personas.suggest_colour(palette::random_seed());
+                }
+                self.personas.0.insert(*uuid, persona);
+            }
+        }
+        let mut added = 0;
+        for chat in export.chats {
+            if self.chat(&chat.uuid).is_none() {
+                chat.save();
+                self.chats.push(chat);
+                added += 1;
+            }
+        }
+        self.save_personas();
+        self.save_index();
+        added
+    }
+}
+
+impl ChatData {
+    /// A readable transcript, one line per message
+    pub fn to_markdown(&self, personas: &Personas) -> String {
+        let mut out = format!("# {}\n\n", self.name);
+        for msg in self.messages.msgs.iter() {
+            let name = personas.get(&msg.persona).map_or("Unknown", |persona| persona.name.as_str());
+            let line = match msg.kind {
+                MessageKind::Speech => format!("**{}**: {}", name, msg.msg),
+                MessageKind::Aside => format!("**{}** _(aside)_: _{}_", name, msg.msg),
+                MessageKind::Narration => format!("_{}_", msg.msg),
+                MessageKind::Event => format!("> {}", msg.msg),
+            };
+            if let Some(parent) = msg.reply_to.and_then(|parent| self.messages.get(&parent)) {
+                out.push_str(&format!("> ↪ {}: {}\n\n", parent.speaker(personas), parent.preview(80)));
+            }
+            out.push_str(&line);
+            if !msg.emotions.is_empty() {
+                let feelings: Vec<String> = msg.emotions.iter().map(|tag| format!("{} {}", tag.name, tag.intensity)).collect();
+                out.push_str(&format!(" _(feeling {})_", feelings.join(", ")));
+            }
+            out.push_str("\n\n");
+        }
+        for moment in Moment::ALL {
+            if let Some(check_in) = self.check_ins.get(moment) {
+                out.push_str(&format!("{} check-in: {}\n\n", moment.name(), check_in.summary()));
+            }
+        }
+        out
+    }
+}
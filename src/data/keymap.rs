@@ -0,0 +1,343 @@
+use dioxus::html::input_data::keyboard_types::{Key, Modifiers};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Send,
+    Newline,
+    NextPersona,
+    PreviousPersona,
+    /// The nth, from 1, persona added to the chat
+    SelectPersona(u8),
+    NewChat,
+    NextChat,
+    PreviousChat,
+    FilterChats,
+    Search,
+    Rename,
+    ShowShortcuts,
+    CommandPalette,
+    ExportChat,
+    DeleteChat,
+    OpenSettings,
+}
+
+impl Action {
+    /// Every action, in the order they're listed in settings
+    pub fn all() -> Vec<Action> {
+        let mut all = vec![Action::Send, Action::Newline, Action::NextPersona, Action::PreviousPersona];
+        all.extend((1..=9).map(Action::SelectPersona));
+        all.extend([
+            Action::NewChat,
+            Action::NextChat,
+            Action::PreviousChat,
+            Action::FilterChats,
+            Action::Search,
+            Action::Rename,
+            Action::ShowShortcuts,
+            Action::CommandPalette,
+            Action::ExportChat,
+            Action::DeleteChat,
+            Action::OpenSettings,
+        ]);
+        all
+    }
+
+    /// Only mean something while writing a message, anywhere else the keys keep their usual job
+    pub fn in_message_input(self) -> bool {
+        matches!(self, Action::Send | Action::Newline | Action::NextPersona | Action::PreviousPersona)
+    }
+
+    pub fn description(self) -> String {
+        match self {
+            Action::Send => "Send the message".to_string(),
+            Action::Newline => "Start a new line".to_string(),
+            Action::NextPersona => "Next persona".to_string(),
+            Action::PreviousPersona => "Previous persona".to_string(),
+            Action::SelectPersona(n) => format!("Speak as persona {n}"),
+            Action::NewChat => "New chat".to_string(),
+            Action::NextChat => "Next chat".to_string(),
+            Action::PreviousChat => "Previous chat".to_string(),
+            Action::FilterChats => "Filter the chats".to_string(),
+            Action::Search => "Search messages".to_string(),
+            Action::Rename => "Rename the chat".to_string(),
+            Action::ShowShortcuts => "Show the shortcuts".to_string(),
+            Action::CommandPalette => "Command palette".to_string(),
+            Action::ExportChat => "Export the chat as Markdown".to_string(),
+            Action::DeleteChat => "Delete the chat".to_string(),
+            Action::OpenSettings => "Settings".to_string(),
+        }
+    }
+}
+
+/// A key with the modifiers that have to be held, written like `Ctrl+Shift+K`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shortcut {
+    /// As the browser names it, `Enter`, `ArrowUp`, `k` or `?`
+    pub key: String,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub meta: bool,
+}
+
+impl Shortcut {
+    pub fn key(key: &str) -> Self {
+        Shortcut {
+            key: key.to_string(),
+            ctrl: false,
+            alt: false,
+            shift: false,
+            meta: false,
+        }
+    }
+
+    pub fn from_event(key: &Key, modifiers: Modifiers) -> Self {
+        Shortcut {
+            key: key.to_string(),
+            ctrl: modifiers.ctrl(),
+            alt: modifiers.alt(),
+            shift: modifiers.shift(),
+            meta: modifiers.meta(),
+        }
+    }
+
+    /// A single character, whose shift state is decided by the keyboard layout rather than the user
+    fn is_character(&self) -> bool {
+        self.key.chars().count() == 1
+    }
+
+    /// Would type a character into a text field rather than do something
+    pub fn types(&self) -> bool {
+        self.is_character() && !self.ctrl && !self.alt && !self.meta
+    }
+
+    /// Whether pressing `key` with `modifiers` triggers this shortcut
+    pub fn matches(&self, key: &Key, modifiers: Modifiers) -> bool {
+        let pressed = Shortcut::from_event(key, modifiers);
+        let shift_matches = self.is_character() || self.shift == pressed.shift;
+        self.key.eq_ignore_ascii_case(&pressed.key)
+            && self.ctrl == pressed.ctrl
+            && self.alt == pressed.alt
+            && self.meta == pressed.meta
+            && shift_matches
+    }
+
+    /// Just a modifier on its own, which is never a whole shortcut
+    pub fn is_modifier(&self) -> bool {
+        matches!(self.key.as_str(), "Control" | "Alt" | "Shift" | "Meta" | "AltGraph" | "OS")
+    }
+}
+
+impl Display for Shortcut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.shift && !self.is_character() {
+            write!(f, "Shift+")?;
+        }
+        if self.meta {
+            write!(f, "Meta+")?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+impl FromStr for Shortcut {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The key itself may be a plus
+        let (modifiers, key) = match s.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None => s.rsplit_once('+').unwrap_or(("", s)),
+        };
+        if key.is_empty() {
+            return Err(format!("{s} has no key"));
+        }
+        let mut shortcut = Shortcut::key(key);
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => shortcut.ctrl = true,
+                "alt" | "option" => shortcut.alt = true,
+                "shift" => shortcut.shift = true,
+                "meta" | "cmd" | "super" => shortcut.meta = true,
+                _ => return Err(format!("{modifier} isn't a modifier, use Ctrl, Alt, Shift or Meta")),
+            }
+        }
+        Ok(shortcut)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Binding {
+    pub action: Action,
+    pub shortcut: Shortcut,
+}
+
+/// Which shortcut does what, saved with the [`Settings`](super::Settings)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "SavedKeymap")]
+pub struct Keymap {
+    pub bindings: Vec<Binding>,
+    /// Actions left without a shortcut on purpose, so they aren't given their default again
+    pub unbound: Vec<Action>,
+}
+
+/// Keymaps used to be saved as just their bindings
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedKeymap {
+    Bindings(Vec<Binding>),
+    Keymap {
+        bindings: Vec<Binding>,
+        #[serde(default)]
+        unbound: Vec<Action>,
+    },
+}
+
+impl From<SavedKeymap> for Keymap {
+    fn from(saved: SavedKeymap) -> Self {
+        let mut keymap = match saved {
+            SavedKeymap::Bindings(bindings) => Keymap { bindings, unbound: Vec::new() },
+            SavedKeymap::Keymap { bindings, unbound } => Keymap { bindings, unbound },
+        };
+        keymap.bind_new_actions();
+        keymap
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let parse = |s: &str| Shortcut::from_str(s).unwrap();
+        let mut bindings = vec![
+            (Action::Send, parse("Enter")),
+            (Action::Newline, parse("Shift+Enter")),
+            (Action::NextPersona, parse("Tab")),
+            (Action::PreviousPersona, parse("Shift+Tab")),
+        ];
+        bindings.extend((1..=9).map(|n| (Action::SelectPersona(n), parse(&format!("Alt+{n}")))));
+        bindings.extend([
+            (Action::NewChat, parse("Alt+n")),
+            (Action::NextChat, parse("Alt+ArrowDown")),
+            (Action::PreviousChat, parse("Alt+ArrowUp")),
+            (Action::FilterChats, parse("Alt+f")),
+            (Action::Search, parse("Ctrl+f")),
+            (Action::Rename, parse("F2")),
+            (Action::ShowShortcuts, parse("?")),
+            (Action::CommandPalette, parse("Ctrl+k")),
+        ]);
+        Keymap {
+            bindings: bindings
+                .into_iter()
+                .map(|(action, shortcut)| Binding { action, shortcut })
+                .collect(),
+            unbound: Vec::new(),
+        }
+    }
+}
+
+impl Keymap {
+    /// What pressing `key` does, if anything
+    pub fn action(&self, key: &Key, modifiers: Modifiers) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.shortcut.matches(key, modifiers))
+            .map(|binding| binding.action)
+    }
+
+    pub fn shortcut(&self, action: Action) -> Option<&Shortcut> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.action == action)
+            .map(|binding| &binding.shortcut)
+    }
+
+    /// Leaves `action` without a shortcut
+    pub fn unbind(&mut self, action: Action) {
+        self.bindings.retain(|binding| binding.action != action);
+        if !self.unbound.contains(&action) {
+            self.unbound.push(action);
+        }
+    }
+
+    /// Binds `action` to `shortcut`, replacing its previous shortcut
+    pub fn bind(&mut self, action: Action, shortcut: Shortcut) {
+        self.unbound.retain(|unbound| *unbound != action);
+        match self.bindings.iter_mut().find(|binding| binding.action == action) {
+            Some(binding) => binding.shortcut = shortcut,
+            None => self.bindings.push(Binding { action, shortcut }),
+        }
+    }
+
+    /// Gives actions added since the keymap was saved their default shortcut, unless it's taken
+    fn bind_new_actions(&mut self) {
+        for default in Keymap::default().bindings {
+            let known = self.shortcut(default.action).is_some() || self.unbound.contains(&default.action);
+            let taken = self.bindings.iter().any(|binding| binding.shortcut == default.shortcut);
+            if !known && !taken {
+                self.bindings.push(default);
+            }
+        }
+    }
+
+    /// The other actions sharing `action`'s shortcut, only the first of which will ever happen
+    pub fn conflicts(&self, action: Action) -> Vec<Action> {
+        let Some(shortcut) = self.shortcut(action) else {
+            return Vec::new();
+        };
+        self.bindings
+            .iter()
+            .filter(|binding| binding.action != action && binding.shortcut == *shortcut)
+            .map(|binding| binding.action)
+            .collect()
+    }
+
+    /// JavaScript that stops the browser's own handling of bound keys, like Tab moving focus,
+    /// while leaving typing in text fields alone
+    pub fn prevent_default_js(&self) -> String {
+        let shortcuts: Vec<serde_json::Value> = self
+            .bindings
+            .iter()
+            .map(|binding| {
+                let shortcut = &binding.shortcut;
+                serde_json::json!({
+                    "key": shortcut.key.to_lowercase(),
+                    "ctrl": shortcut.ctrl,
+                    "alt": shortcut.alt,
+                    "shift": shortcut.shift,
+                    "meta": shortcut.meta,
+                    "character": shortcut.key.chars().count() == 1,
+                    "types": shortcut.types(),
+                    "messageInput": binding.action.in_message_input(),
+                })
+            })
+            .collect();
+        format!(
+            r#"
+            window.letMeTalkShortcuts = {};
+            if (!window.letMeTalkShortcutsInstalled) {{
+                window.letMeTalkShortcutsInstalled = true;
+                document.addEventListener("keydown", (evt) => {{
+                    const target = evt.target;
+                    const editable = target.tagName === "TEXTAREA" || target.tagName === "INPUT" || target.isContentEditable;
+                    const inMessage = typeof target.id === "string" && target.id.startsWith("mi_");
+                    const bound = window.letMeTalkShortcuts.some((s) =>
+                        s.key === evt.key.toLowerCase()
+                        && s.ctrl === evt.ctrlKey && s.alt === evt.altKey && s.meta === evt.metaKey
+                        && (s.character || s.shift === evt.shiftKey)
+                        && !(s.types && editable)
+                        && (!s.messageInput || inMessage));
+                    if (bound) evt.preventDefault();
+                }});
+            }}
+            "#,
+            serde_json::Value::Array(shortcuts)
+        )
+    }
+}
@@ -1,21 +1,30 @@
-use crate::colours::Rgb;
+use super::fuzzy_score;
+use crate::colours::{palette, vision, Oklab, Rgb};
 use indexmap::{indexmap, IndexMap};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Persona {
     pub name: String,
     pub colour: Rgb,
 }
 
-#[derive(Clone, Default, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct Personas(pub IndexMap<Uuid, Persona>);
 
 impl Personas {
     pub fn new(persona: Persona) -> Self {
         Personas(indexmap! { Uuid::new_v4() => persona })
     }
+
+    /// What a fresh install starts with
+    pub fn starter() -> Self {
+        Personas::new(Persona {
+            name: "Me".to_string(),
+            colour: Rgb(0x49, 0x55, 0x65),
+        })
+    }
     pub fn get(&self, key: &Uuid) -> Option<&Persona> {
         self.0.get(key)
     }
@@ -35,6 +44,64 @@ impl Personas {
         uuid
     }
 
+    pub fn colours(&self) -> Vec<Rgb> {
+        self.0.values().map(|persona| persona.colour).collect()
+    }
+
+    /// A colour that stands apart from every persona's, see [`palette::distinct_colour`]
+    pub fn suggest_colour(&self, seed: u64) -> Rgb {
+        palette::distinct_colour(&self.colours(), seed)
+    }
+
+    /// Whether `colour` is hard to tell apart from some persona's, see [`vision::CONFUSABLE_DISTANCE`]
+    pub fn colour_clashes(&self, colour: Rgb) -> bool {
+        let colour = Oklab::from(colour);
+        self.colours().into_iter().any(|other| colour.distance(Oklab::from(other)) < vision::CONFUSABLE_DISTANCE)
+    }
+
+    /// Adds a persona with a colour picked to stand apart from the others
+    pub fn push_named(&mut self, name: String) -> Uuid {
+        let colour = self.suggest_colour(palette::random_seed());
+        self.push(Persona { name, colour })
+    }
+
+    /// The persona called `name`, ignoring case
+    pub fn find_by_name(&self, name: &str) -> Option<Uuid> {
+        self.iter()
+            .find(|(_, persona)| persona.name.eq_ignore_ascii_case(name.trim()))
+            .map(|(uuid, _)| *uuid)
+    }
+
+    /// The shape shown with this persona's colour, see [`vision::marker`]
+    pub fn marker(&self, key: &Uuid) -> &'static str {
+        vision::marker(self.get_index_of(key).unwrap_or(0))
+    }
+
+    /// The persona a message is addressed to with a leading `@name`, and the rest of the message.
+    /// Names are matched ignoring case, the longest one wins so `@Inner Critic` beats `@Inner`.
+    pub fn parse_mention<'a>(&self, msg: &'a str) -> Option<(Uuid, &'a str)> {
+        let rest = msg.strip_prefix('@')?;
+        self.iter()
+            .filter(|(_, persona)| {
+                let name = persona.name.as_str();
+                !name.is_empty()
+                    && rest.get(..name.len()).is_some_and(|start| start.eq_ignore_ascii_case(name))
+                    && rest[name.len()..].chars().next().map_or(true, char::is_whitespace)
+            })
+            .max_by_key(|(_, persona)| persona.name.len())
+            .map(|(uuid, persona)| (*uuid, rest[persona.name.len()..].trim_start()))
+    }
+
+    /// Personas whose name fits a partly typed `@mention`, best first
+    pub fn mention_candidates(&self, query: &str) -> Vec<Uuid> {
+        let mut scored: Vec<(i64, Uuid)> = self
+            .iter()
+            .filter_map(|(uuid, persona)| Some((fuzzy_score(query, &persona.name)?, *uuid)))
+            .collect();
+        scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+        scored.into_iter().map(|(_, uuid)| uuid).collect()
+    }
+
     pub fn count(&self) -> usize {
         self.0.len()
     }
@@ -0,0 +1,103 @@
+use super::{ChatData, ChatTemplate, Keymap, Personas};
+use crate::colours::theme::Theme;
+use crate::storage;
+use chrono::format::{Item, StrftimeItems};
+use indexmap::IndexSet;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// What new chats are named after when nothing else is set
+pub const DEFAULT_DATE_FORMAT: &str = "%a, %h %d, %Y";
+pub const DEFAULT_FONT_SIZE: u8 = 16;
+pub const FONT_SIZES: std::ops::RangeInclusive<u8> = 12..=24;
+
+/// Preferences, saved under `ifs_settings`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    /// Keyboard shortcuts, sending included
+    #[serde(default)]
+    pub keymap: Keymap,
+    /// A chrono format string new chats are named with
+    pub date_format: String,
+    /// Personas added to new chats, the first one starts active. Empty means the first persona
+    pub default_personas: IndexSet<Uuid>,
+    pub theme: Theme,
+    /// Root font size in pixels
+    pub font_size: u8,
+    /// Offered when starting a chat
+    #[serde(default)]
+    pub templates: Vec<ChatTemplate>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            keymap: Keymap::default(),
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            default_personas: IndexSet::new(),
+            theme: Theme::default(),
+            font_size: DEFAULT_FONT_SIZE,
+            templates: Vec::new(),
+        }
+    }
+}
+
+/// Whether chrono can format with `format`, it panics on invalid ones otherwise
+pub fn valid_date_format(format: &str) -> bool {
+    StrftimeItems::new(format).all(|item| item != Item::Error)
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        storage::retrieve("ifs_settings", Settings::default)
+    }
+
+    pub fn save(&self) {
+        storage::store("ifs_settings", self.clone());
+    }
+
+    /// Today's date in [`Settings::date_format`], or the default format if that one is invalid
+    pub fn chat_name(&self) -> String {
+        let format = if valid_date_format(&self.date_format) {
+            self.date_format.as_str()
+        } else {
+            DEFAULT_DATE_FORMAT
+        };
+        chrono::Local::now().format(format).to_string()
+    }
+
+    /// The default personas that still exist, falling back to the first persona
+    pub fn starting_personas(&self, personas: &Personas) -> IndexSet<Uuid> {
+        let mut starting: IndexSet<Uuid> = self
+            .default_personas
+            .iter()
+            .filter(|uuid| personas.get(uuid).is_some())
+            .copied()
+            .collect();
+        if starting.is_empty() {
+            starting.extend(personas.get_index(0).map(|(uuid, _)| *uuid));
+        }
+        starting
+    }
+
+    /// A chat named and peopled as these settings ask
+    pub fn new_chat(&self, personas: &Personas) -> ChatData {
+        let added_personas = self.starting_personas(personas);
+        ChatData {
+            name: self.chat_name(),
+            active_persona: added_personas[0],
+            added_personas,
+            ..ChatData::new(*personas.get_index(0).unwrap().0)
+        }
+    }
+
+    /// The template called `name`, in any case
+    pub fn find_template(&self, name: &str) -> Option<&ChatTemplate> {
+        self.templates.iter().find(|template| template.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The stylesheet for the theme and font size
+    pub fn css(&self) -> String {
+        format!("{} html {{ font-size: {}px; }}", self.theme.css(), self.font_size)
+    }
+}
@@ -0,0 +1,333 @@
+use super::{AppState, Chat, ChatData, JournalExport, MessageKind, Persona};
+use crate::colours::Rgb;
+use crate::components::download_js;
+use dioxus::html::prelude::{EvalError, UseEval};
+use dioxus::prelude::ScopeState;
+use std::{rc::Rc, str::FromStr, time::Duration};
+
+/// What a command tells the user once it's run, if anything, or why it couldn't run
+pub type SlashResult = Result<Option<String>, String>;
+
+/// What a command can act on
+pub struct SlashContext<'a> {
+    pub cx: &'a ScopeState,
+    pub app_state: AppState,
+    pub chat: Chat,
+    pub eval: Rc<dyn Fn(&str) -> Result<UseEval, EvalError>>,
+    /// For commands about the other commands
+    pub registry: &'a SlashRegistry,
+}
+
+/// A `/name args` command typed into the message input instead of a message
+#[derive(Clone, Copy)]
+pub struct SlashCommand {
+    pub name: &'static str,
+    /// The arguments, like `<name> [colour]`
+    pub usage: &'static str,
+    pub help: &'static str,
+    /// Called with everything after the name, trimmed
+    pub run: fn(&SlashContext, &str) -> SlashResult,
+}
+
+impl SlashCommand {
+    /// `/name usage`, as shown in the help
+    pub fn synopsis(&self) -> String {
+        if self.usage.is_empty() {
+            format!("/{}", self.name)
+        } else {
+            format!("/{} {}", self.name, self.usage)
+        }
+    }
+}
+
+/// The command name and arguments of `input`, or None if it's a message.
+/// A doubled slash, `//like this`, is a message starting with a slash.
+pub fn parse_slash(input: &str) -> Option<(&str, &str)> {
+    let rest = input.strip_prefix('/')?;
+    if !rest.starts_with(|c: char| c.is_alphabetic()) {
+        return None;
+    }
+    let (name, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    Some((name, args.trim()))
+}
+
+/// How long `10m`, `90s`, `1h30m` or a bare number of minutes is
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    if let Ok(minutes) = text.parse::<u64>() {
+        return Some(Duration::from_secs(minutes * 60));
+    }
+    let mut seconds = 0;
+    let mut number = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            _ => return None,
+        };
+        seconds += number.parse::<u64>().ok()? * unit;
+        number.clear();
+    }
+    (number.is_empty() && seconds > 0).then(|| Duration::from_secs(seconds))
+}
+
+/// The slash commands the message input understands, provided as context by the layout.
+/// Anything registered here gets autocomplete, help and running without touching the input.
+#[derive(Clone, Default)]
+pub struct SlashRegistry {
+    commands: Vec<SlashCommand>,
+}
+
+impl SlashRegistry {
+    pub fn new() -> Self {
+        SlashRegistry::default()
+    }
+
+    /// The commands that come with the app
+    pub fn builtin() -> Self {
+        let mut registry = SlashRegistry::new();
+        registry.register(SlashCommand {
+            name: "rename",
+            usage: "<name>",
+            help: "Rename the chat",
+            run: rename,
+        });
+        registry.register(SlashCommand {
+            name: "persona",
+            usage: "new <name> [colour]",
+            help: "Create a persona and speak as them",
+            run: persona,
+        });
+        registry.register(SlashCommand {
+            name: "note",
+            usage: "<text>",
+            help: "Describe the scene or add a facilitator note",
+            run: note,
+        });
+        registry.register(SlashCommand {
+            name: "aside",
+            usage: "<text>",
+            help: "A thought or body sensation of the current persona",
+            run: aside,
+        });
+        registry.register(SlashCommand {
+            name: "event",
+            usage: "<text>",
+            help: "Mark something that happened",
+            run: event,
+        });
+        registry.register(SlashCommand {
+            name: "timer",
+            usage: "<duration>",
+            help: "Note in the chat when the time is up, like 10m or 1h30m",
+            run: timer,
+        });
+        registry.register(SlashCommand {
+            name: "guide",
+            usage: "<guide>|next|end",
+            help: "Start a guided session, move to its next step or end it",
+            run: guide,
+        });
+        registry.register(SlashCommand {
+            name: "export",
+            usage: "md|json",
+            help: "Download the chat",
+            run: export,
+        });
+        registry.register(SlashCommand {
+            name: "undo",
+            usage: "",
+            help: "Remove the last message",
+            run: undo,
+        });
+        registry.register(SlashCommand {
+            name: "help",
+            usage: "[command]",
+            help: "List the commands",
+            run: help,
+        });
+        registry
+    }
+
+    /// Adds `command`, replacing any with the same name
+    pub fn register(&mut self, command: SlashCommand) {
+        match self.commands.iter_mut().find(|existing| existing.name == command.name) {
+            Some(existing) => *existing = command,
+            None => self.commands.push(command),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SlashCommand> {
+        self.commands.iter().find(|command| command.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn commands(&self) -> std::slice::Iter<SlashCommand> {
+        self.commands.iter()
+    }
+
+    /// Commands whose name fits a partly typed one, best first
+    pub fn completions(&self, partial: &str) -> Vec<&SlashCommand> {
+        let mut scored: Vec<(i64, &SlashCommand)> = self
+            .commands
+            .iter()
+            .filter_map(|command| Some((super::fuzzy_score(partial, command.name)?, command)))
+            .collect();
+        scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+        scored.into_iter().map(|(_, command)| command).collect()
+    }
+
+    /// Runs the command `input` names, or None if it's a message
+    pub fn run(&self, ctx: &SlashContext, input: &str) -> Option<SlashResult> {
+        let (name, args) = parse_slash(input)?;
+        Some(match self.get(name) {
+            Some(command) => (command.run)(ctx, args),
+            None => Err(format!("There's no /{name}, /help lists the commands, start with // to send a slash")),
+        })
+    }
+}
+
+fn rename(ctx: &SlashContext, args: &str) -> SlashResult {
+    if args.is_empty() {
+        return Err("Give the chat a name, /rename <name>".to_string());
+    }
+    ctx.chat.name.set(args.to_string());
+    ctx.chat.save();
+    Ok(Some(format!("Renamed to {args}")))
+}
+
+fn persona(ctx: &SlashContext, args: &str) -> SlashResult {
+    let usage = "/persona new <name> [colour]";
+    let (verb, args) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+    let args = args.trim();
+    if verb != "new" || args.is_empty() {
+        return Err(format!("Give the persona a name, {usage}"));
+    }
+    // A single word is always the name, even if it's also a colour
+    let (name, colour) = match args.rsplit_once(char::is_whitespace) {
+        Some((name, colour)) if Rgb::from_str(colour).is_ok() => (name.trim(), Rgb::from_str(colour).ok()),
+        _ => (args, None),
+    };
+    let mut personas = ctx.app_state.personas.write();
+    let uuid = match colour {
+        Some(colour) => personas.push(Persona { name: name.to_string(), colour }),
+        None => personas.push_named(name.to_string()),
+    };
+    drop(personas);
+    ctx.chat.add_persona(uuid);
+    ctx.chat.save();
+    Ok(Some(format!("Speaking as {name}")))
+}
+
+fn note(ctx: &SlashContext, args: &str) -> SlashResult {
+    post(ctx, MessageKind::Narration, args, "/note <text>")
+}
+
+fn aside(ctx: &SlashContext, args: &str) -> SlashResult {
+    post(ctx, MessageKind::Aside, args, "/aside <text>")
+}
+
+fn event(ctx: &SlashContext, args: &str) -> SlashResult {
+    post(ctx, MessageKind::Event, args, "/event <text>")
+}
+
+fn post(ctx: &SlashContext, kind: MessageKind, args: &str, usage: &str) -> SlashResult {
+    if args.is_empty() {
+        return Err(format!("Write the {}, {usage}", kind.name().to_lowercase()));
+    }
+    ctx.chat.post(kind, *ctx.chat.active_persona.read(), args.to_string());
+    ctx.chat.save();
+    Ok(None)
+}
+
+fn timer(ctx: &SlashContext, args: &str) -> SlashResult {
+    let duration = parse_duration(args).ok_or_else(|| format!("{args:?} isn't a duration, try 10m, 90s or 1h30m"))?;
+    let js = format!("setTimeout(() => dioxus.send(true), {});", duration.as_millis());
+    let waiting = (ctx.eval)(&js).map_err(|e| format!("Couldn't start the timer: {e:?}"))?;
+    let chat = ctx.chat;
+    let chats = ctx.app_state.chats;
+    let label = args.to_string();
+    // Keeps running if the chat is closed, the message lands in it unless it's gone by then
+    ctx.cx.spawn_forever(async move {
+        if waiting.recv().await.is_ok() && chats.read().get(chat.uuid()).is_some() {
+            let persona = *chat.active_persona.read();
+            chat.post(MessageKind::Event, persona, format!("⏱ {label} timer is up"));
+            chat.save();
+        }
+    });
+    Ok(Some(format!("Timer set for {args}")))
+}
+
+fn guide(ctx: &SlashContext, args: &str) -> SlashResult {
+    let personas = ctx.app_state.personas.read();
+    let following = ctx.chat.session.read().is_some();
+    match args.to_lowercase().as_str() {
+        "" => {
+            let names: Vec<String> = ctx.app_state.guides.read().all().into_iter().map(|guide| guide.name).collect();
+            Ok(Some(format!("Start one of {} with /guide <name>", names.join(", "))))
+        }
+        "next" | "end" if !following => Err("There's no guided session, start one with /guide <name>".to_string()),
+        "next" => match ctx.chat.next_guide_step(&personas) {
+            true => Ok(None),
+            false => Ok(Some("That was the last step".to_string())),
+        },
+        "end" => {
+            ctx.chat.end_guide();
+            Ok(None)
+        }
+        _ => {
+            let guide = ctx
+                .app_state
+                .guides
+                .read()
+                .find(args)
+                .ok_or_else(|| format!("There's no guide called {args}, /guide lists them"))?;
+            let name = guide.name.clone();
+            ctx.chat.start_guide(guide, &personas);
+            Ok(Some(format!("Following {name}, /guide next moves on")))
+        }
+    }
+}
+
+fn export(ctx: &SlashContext, args: &str) -> SlashResult {
+    let chat = ChatData::from(&ctx.chat);
+    let personas = ctx.app_state.personas.read();
+    let js = match args {
+        "" | "md" | "markdown" => download_js(&format!("{}.md", chat.name), "text/markdown", &chat.to_markdown(&personas)),
+        "json" => {
+            let export = JournalExport {
+                personas: personas.clone(),
+                chats: vec![chat.clone()],
+            };
+            let json = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+            download_js(&format!("{}.json", chat.name), "application/json", &json)
+        }
+        _ => return Err(format!("Export as md or json, not {args}")),
+    };
+    (ctx.eval)(&js).map_err(|e| format!("Couldn't export: {e:?}"))?;
+    Ok(None)
+}
+
+fn undo(ctx: &SlashContext, _args: &str) -> SlashResult {
+    let removed = ctx.chat.messages.write().msgs.pop().ok_or("There's nothing to undo")?;
+    ctx.chat.save();
+    Ok(Some(format!("Removed \"{}\"", removed.msg)))
+}
+
+fn help(ctx: &SlashContext, args: &str) -> SlashResult {
+    let name = args.trim_start_matches('/');
+    if !name.is_empty() {
+        let command = ctx.registry.get(name).ok_or_else(|| format!("There's no /{name}"))?;
+        return Ok(Some(format!("{} – {}", command.synopsis(), command.help)));
+    }
+    let lines: Vec<String> = ctx
+        .registry
+        .commands()
+        .map(|command| format!("{} – {}", command.synopsis(), command.help))
+        .collect();
+    Ok(Some(lines.join("\n")))
+}
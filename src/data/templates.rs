@@ -0,0 +1,72 @@
+use super::{valid_date_format, ChatData, MessageKind, Personas, DEFAULT_DATE_FORMAT};
+use indexmap::IndexSet;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A reusable way to start a chat, saved with the [`Settings`](super::Settings)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChatTemplate {
+    pub uuid: Uuid,
+    pub name: String,
+    /// Added to chats started from it, ones that have since been deleted are skipped
+    pub personas: IndexSet<Uuid>,
+    /// Who is active to begin with, the first of `personas` when unset or not among them
+    pub starting_persona: Option<Uuid>,
+    /// Posted as narration to open the chat, nothing is posted when it's blank
+    pub opening: String,
+    /// A chrono format string chats are named with, text around the `%` codes is kept as it is
+    pub name_format: String,
+}
+
+impl ChatTemplate {
+    pub fn new(name: String) -> Self {
+        ChatTemplate {
+            uuid: Uuid::new_v4(),
+            name_format: format!("{}, {DEFAULT_DATE_FORMAT}", name.replace('%', "%%")),
+            name,
+            personas: IndexSet::new(),
+            starting_persona: None,
+            opening: String::new(),
+        }
+    }
+
+    /// Today's name for a chat from this template, falling back to the template's name
+    /// and the default date when the format is invalid
+    pub fn chat_name(&self) -> String {
+        let now = chrono::Local::now();
+        if valid_date_format(&self.name_format) {
+            now.format(&self.name_format).to_string()
+        } else {
+            format!("{}, {}", self.name, now.format(DEFAULT_DATE_FORMAT))
+        }
+    }
+
+    /// The personas that still exist, falling back to the first persona
+    pub fn starting_personas(&self, personas: &Personas) -> IndexSet<Uuid> {
+        let mut starting: IndexSet<Uuid> =
+            self.personas.iter().filter(|uuid| personas.get(uuid).is_some()).copied().collect();
+        if starting.is_empty() {
+            starting.extend(personas.get_index(0).map(|(uuid, _)| *uuid));
+        }
+        starting
+    }
+
+    /// A chat named, peopled and opened as this template asks
+    pub fn new_chat(&self, personas: &Personas) -> ChatData {
+        let added_personas = self.starting_personas(personas);
+        let active_persona = self
+            .starting_persona
+            .filter(|uuid| added_personas.contains(uuid))
+            .unwrap_or(added_personas[0]);
+        let mut chat = ChatData {
+            name: self.chat_name(),
+            active_persona,
+            added_personas,
+            ..ChatData::new(active_persona)
+        };
+        if !self.opening.trim().is_empty() {
+            chat.post(MessageKind::Narration, active_persona, self.opening.trim().to_string());
+        }
+        chat
+    }
+}
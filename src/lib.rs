@@ -0,0 +1,10 @@
+#![allow(non_snake_case, unused)]
+pub mod app;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cli;
+pub mod colours;
+pub mod components;
+pub mod data;
+pub mod pages;
+pub mod storage;
+pub mod sync;
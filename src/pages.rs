@@ -1 +1,15 @@
 pub mod chat;
+pub mod persona;
+pub mod settings;
+pub mod trends;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Page {
+    #[default]
+    Chat,
+    Settings,
+    /// How the check-ins have changed across chats
+    Trends,
+    /// What a persona has said and felt across chats
+    Persona(uuid::Uuid),
+}
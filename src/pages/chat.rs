@@ -8,7 +8,7 @@ use dioxus::html::input_data::keyboard_types::Modifiers;
 use indexmap::{indexmap, IndexSet};
 use uuid::Uuid;
 
-use crate::colours::*;
+use crate::colours::{theme::bubble_style, *};
 use dioxus::prelude::*;
 use dioxus_signals::*;
 
@@ -17,25 +17,39 @@ pub fn ChatPage(cx: Scope, chat: Chat) -> Element {
     let add_persona_id = use_signal(cx, || format!("pd_{}", chat.uuid()));
     let input_id = use_signal(cx, || format!("mi_{}", chat.uuid()));
     let add_new_persona_id = use_signal(cx, || format!("npd_{}", chat.uuid()));
-    let js = format!(r#"
-        document.addEventListener("keyup", (evt) => {{
-            if (evt.target.id !== "{input_id}") return;
-            else if (evt.key === "Tab") {{
-                evt.preventDefault();
-            }}
-        }});
-        document.addEventListener("keydown", (evt) => {{
-            if (evt.target.id !== "{input_id}") return;
-            else if (evt.key === "Tab") {{
-                evt.preventDefault();
-            }}
-          }});
-    "#);
-    use_eval(cx)(&js);
-
-    let on_send = |_| {
-        chat.send();
-        chat.save();
+    let app_state = AppState::use_app_context(cx);
+    let registry = use_context::<SlashRegistry>(cx).expect("the layout provides the slash commands");
+    let eval = use_eval(cx);
+    // What the last slash command said
+    let status = use_signal(cx, String::new);
+    let kind = use_signal(cx, MessageKind::default);
+    let replying_to = use_signal(cx, || None::<Uuid>);
+    // The message whose line of dialogue is followed, everything is shown without one
+    let thread = use_signal(cx, || None::<Uuid>);
+    // Messages picked to move or copy to another chat
+    let selected = use_signal(cx, IndexSet::<Uuid>::new);
+    let on_send = move |_| {
+        let msg = chat.current_message.read().clone();
+        let ctx = SlashContext { cx, app_state, chat: *chat, eval: eval.clone(), registry };
+        match registry.run(&ctx, &msg) {
+            Some(Ok(said)) => {
+                status.set(said.unwrap_or_default());
+                chat.current_message.set(String::new());
+            }
+            // Kept so it can be fixed
+            Some(Err(error)) => status.set(error),
+            None => {
+                if msg.starts_with("//") {
+                    chat.current_message.set(msg[1..].to_string());
+                }
+                // The reply may be to a message in a chat that's since been switched away from
+                let reply_to = replying_to.read().filter(|parent| chat.messages.read().get(parent).is_some());
+                if chat.send_mentioned(&app_state.personas.read(), *kind.read(), reply_to) {
+                    replying_to.set(None);
+                }
+                chat.save();
+            }
+        }
     };
 
 
@@ -43,14 +57,32 @@ pub fn ChatPage(cx: Scope, chat: Chat) -> Element {
         div {
             class: "grid h-full w-full",
             style: "grid-template-rows: minmax(0, 1fr) auto auto;",
-            div { MessageBox {
-                messages: chat.messages
-            } }
-            div { MessageInput {
+            div { class: "relative h-full",
+                MessageBox {
+                    messages: chat.messages,
+                    input_id: "{input_id}",
+                    replying_to: replying_to,
+                    thread: thread,
+                    selected: selected,
+                    on_branch: move |message| AppState::branch_chat(cx, *chat, message),
+                }
+                if !selected.read().is_empty() {
+                    rsx! { SelectionBar { chat: *chat, selected: selected } }
+                }
+            }
+            div { CheckInBar { chat: *chat }
+            GuideBar { chat: *chat }
+            ReplyingTo {
+                messages: chat.messages,
+                replying_to: replying_to,
+            }
+            MessageInput {
                 id: "{input_id}",
                 current_message: chat.current_message,
                 active_persona: chat.active_persona,
                 added_personas: chat.added_personas,
+                status: status,
+                kind: kind,
                 on_send: on_send,
             } }
             div { BottomBar {
@@ -71,7 +103,7 @@ pub fn ChatPage(cx: Scope, chat: Chat) -> Element {
         AddNewPersonaDialog {
             id: "{add_new_persona_id}",
             on_create: move |(persona_name, persona_colour)| {
-                let p_uuid = AppState::personas(cx).write().push(Persona {
+                let p_uuid = app_state.personas.write().push(Persona {
                         name: persona_name,
                         colour: persona_colour,
                     });
@@ -85,13 +117,14 @@ pub fn ChatPage(cx: Scope, chat: Chat) -> Element {
 #[component]
 fn AddPersonaDialog<'a>(cx: Scope, id: &'a str, input_id: &'a str, add_new_persona_id: &'a str, added_personas: Signal<IndexSet<Uuid>>, active_persona: Signal<Uuid>) -> Element {
     let personas = AppState::personas(cx);
+    let theme = AppState::settings(cx).read().theme;
     cx.render(rsx! {
-        dialog { id: "{id}", class: "p-4 pt-7, rounded-2xl max-w-full",
+        dialog { id: "{id}", class: "p-4 pt-7 rounded-2xl max-w-full bg-surface text-ink",
             div { class: "flex flex-col gap-2",
                 div { class: "grid grid-cols-2 place-content-between",
                     "My Personas"
                     button {
-                        class: "bg-gray-300",
+                        class: "bg-raised",
                         onclick: move |_| {
                             use_eval(cx)(&format!(r#"document.getElementById("{add_new_persona_id}").showModal();"#))
                                 .unwrap();
@@ -119,7 +152,8 @@ fn AddPersonaDialog<'a>(cx: Scope, id: &'a str, input_id: &'a str, add_new_perso
                                     }
                                 },
                                 PersonaIcon {
-                                    colour: persona.colour
+                                    colour: theme.persona_colour(persona.colour),
+                                    marker: theme.markers.then(|| personas.read().marker(&uuid)),
                                 },
                                 "{persona.name}"
                             }
@@ -132,41 +166,551 @@ fn AddPersonaDialog<'a>(cx: Scope, id: &'a str, input_id: &'a str, add_new_perso
 }
 
 #[component]
-pub fn MessageBox(cx: Scope, messages: Signal<Messages>) -> Element {
+pub fn MessageBox<'a>(cx: Scope, messages: Signal<Messages>, input_id: &'a str, replying_to: Signal<Option<Uuid>>, thread: Signal<Option<Uuid>>, selected: Signal<IndexSet<Uuid>>, on_branch: EventHandler<'a, Uuid>) -> Element {
     let personas = AppState::personas(cx);
+    let theme = AppState::settings(cx).read().theme;
+    let all = messages.read();
+    let following = thread.read().map(|uuid| all.thread(&uuid)).filter(|thread| !thread.is_empty());
+    let shown: Vec<&Message> = all
+        .msgs
+        .iter()
+        .filter(|msg| following.as_ref().map_or(true, |thread| thread.contains(&msg.uuid)))
+        .collect();
+    let last = shown.len().saturating_sub(1);
 
     cx.render(rsx! {
         div { class: "flex flex-col border rounded-xl p-4 min-h-full w-full gap-2 max-h-full overflow-y-scroll",
-            for (i , msg) in messages.read().msgs.iter().enumerate() {
-                if let Some(persona) = personas.read().get(&msg.persona) {
-                    rsx! {
-                        div { 
-                            key: "{msg.uuid}",
-                            // If it's the first message we want to push it to the bottom of the div
-                            class: if i == 0 { "flex-col gap-2 mt-auto" } else { "flex-col gap-2" },
-                            // If it's the first message or a different persona than previous then render the persona info
-                            if i == 0 || !msg.persona.eq(&messages.read().msgs.get(i-1).unwrap().persona) {
-                                rsx! {
+            if following.is_some() {
+                rsx! {
+                    div { class: "flex gap-2 items-center text-sm",
+                        "Following a thread"
+                        button {
+                            class: "bg-raised px-2 rounded-lg",
+                            onclick: move |_| thread.set(None),
+                            "Show all"
+                        }
+                    }
+                }
+            }
+            for (i , msg) in shown.iter().enumerate() {
+                match msg.kind {
+                    MessageKind::Narration | MessageKind::Event => {
+                        let style = if msg.kind == MessageKind::Narration {
+                            "px-6 italic"
+                        } else {
+                            "text-xs uppercase tracking-wide opacity-70"
+                        };
+                        rsx! {
+                            div {
+                                key: "{msg.uuid}",
+                                id: "msg_{msg.uuid}",
+                                // If it's the first message we want to push it to the bottom of the div
+                                class: if i == 0 { "flex flex-col items-center gap-1 mt-auto" } else { "flex flex-col items-center gap-1" },
+                                if let Some(parent) = msg.reply_to {
+                                    rsx! { ReplyQuote { messages: *messages, parent: parent } }
+                                }
+                                p {
+                                    class: "w-full text-center {style}",
+                                    onmounted: move |cx2| {
+                                        if i == last {
+                                            cx2.inner().scroll_to(ScrollBehavior::Smooth);
+                                        }
+                                    },
+                                    "{msg.msg}"
+                                }
+                                if !msg.emotions.is_empty() {
+                                    rsx! { EmotionChips { emotions: msg.emotions.clone() } }
+                                }
+                                if msg.kind == MessageKind::Narration {
+                                    rsx! { MessageActions { uuid: msg.uuid, messages: *messages, input_id: input_id, replying_to: *replying_to, thread: *thread, selected: *selected, on_branch: move |uuid| on_branch.call(uuid) } }
+                                }
+                            }
+                        }
+                    }
+                    MessageKind::Speech | MessageKind::Aside => rsx! {
+                        if let Some(persona) = personas.read().get(&msg.persona) {
+                            // Asides are thought rather than said, so they're outlined instead of filled
+                            let bubble = if msg.kind == MessageKind::Aside { "italic border-2 border-dashed opacity-80" } else { "" };
+                            let speaker = msg.persona;
+                            let starts_group = i == 0 || {
+                                let previous = shown[i - 1];
+                                !previous.kind.has_speaker() || previous.persona != msg.persona
+                            };
+                            rsx! {
+                                div {
+                                    key: "{msg.uuid}",
+                                    id: "msg_{msg.uuid}",
+                                    class: if i == 0 { "flex flex-col gap-1 mt-auto" } else { "flex flex-col gap-1" },
+                                    // If it's the first message or follows someone else or narration then render the persona info
+                                    if starts_group {
+                                        rsx! {
+                                            div {
+                                                class: "flex items-center",
+                                                PersonaIcon {
+                                                    colour: theme.persona_colour(persona.colour),
+                                                    marker: theme.markers.then(|| personas.read().marker(&msg.persona)),
+                                                }
+                                                button {
+                                                    title: "See how they've been feeling",
+                                                    onclick: move |_| AppState::page(cx).set(crate::pages::Page::Persona(speaker)),
+                                                    "{persona.name}"
+                                                }
+                                            }
+                                        }
+                                    }
+                                    // Replies quote what they answer even within a group
+                                    if let Some(parent) = msg.reply_to {
+                                        rsx! { ReplyQuote { messages: *messages, parent: parent } }
+                                    }
                                     div {
-                                        class: "flex items-center",
-                                        PersonaIcon { colour: persona.colour }
-                                        span { "{persona.name}" }
+                                        class: "bubble rounded-lg px-2 py-1 w-fit text-left {bubble}",
+                                        style: "{bubble_style(theme.persona_colour(persona.colour))}",
+                                        onmounted: move |cx2| {
+                                            if i == last {
+                                                cx2.inner().scroll_to(ScrollBehavior::Smooth);
+                                            }
+                                        },
+                                        if theme.markers {
+                                            rsx! { span { class: "mr-1", "{personas.read().marker(&msg.persona)}" } }
+                                        }
+                                        span { "{msg.msg}" }
                                     }
+                                    if !msg.emotions.is_empty() {
+                                        rsx! { EmotionChips { emotions: msg.emotions.clone() } }
+                                    }
+                                    MessageActions { uuid: msg.uuid, messages: *messages, input_id: input_id, replying_to: *replying_to, thread: *thread, selected: *selected, on_branch: move |uuid| on_branch.call(uuid) }
                                 }
                             }
-                            div {
-                                class: "rounded-lg px-2 py-1 w-fit text-left",
-                                style: "{Colour::BgColour(persona.colour)} {text_colour_from_bg(persona.colour)}",
-                                onmounted: move |cx2| {
-                                    if i == messages.read().msgs.len()-1 {
-                                        cx2.inner().scroll_to(ScrollBehavior::Smooth);
-                                    }
-                                },
-                                span { "{msg.msg}" }
+                        }
+                    },
+                }
+            }
+        }
+    })
+}
+
+/// The start of the message being replied to, click to scroll to it
+#[component]
+fn ReplyQuote(cx: Scope, messages: Signal<Messages>, parent: Uuid) -> Element {
+    let personas = AppState::personas(cx);
+    let eval = use_eval(cx);
+    let quote = match messages.read().get(parent) {
+        Some(msg) => format!("{}: {}", msg.speaker(&personas.read()), msg.preview(80)),
+        None => "A removed message".to_string(),
+    };
+
+    cx.render(rsx! {
+        button {
+            class: "text-xs text-left border-l-4 pl-2 opacity-80 w-fit max-w-full truncate",
+            title: "Jump to the message",
+            onclick: move |_| {
+                let js = format!(r#"
+                    el = document.getElementById("msg_{parent}");
+                    if (el) el.scrollIntoView({{ behavior: "smooth", block: "center" }});
+                "#);
+                eval(&js).unwrap();
+            },
+            "↪ {quote}"
+        }
+    })
+}
+
+/// Picking, replying to, following the thread of, tagging and branching the chat from a message
+#[component]
+fn MessageActions<'a>(cx: Scope, uuid: Uuid, messages: Signal<Messages>, input_id: &'a str, replying_to: Signal<Option<Uuid>>, thread: Signal<Option<Uuid>>, selected: Signal<IndexSet<Uuid>>, on_branch: EventHandler<'a, Uuid>) -> Element {
+    let eval = use_eval(cx);
+    let uuid = *uuid;
+    let replies = messages.read().reply_count(&uuid);
+    let in_thread = replies > 0 || messages.read().get(&uuid).is_some_and(|msg| msg.reply_to.is_some());
+    let following = *thread.read() == Some(uuid);
+    let replies_label = match replies {
+        0 => "Thread".to_string(),
+        1 => "1 reply".to_string(),
+        n => format!("{n} replies"),
+    };
+
+    let picked = selected.read().contains(&uuid);
+    let tagging = use_signal(cx, || false);
+
+    cx.render(rsx! {
+        div { class: "flex gap-2 items-center text-xs opacity-60",
+            input {
+                r#type: "checkbox",
+                title: "Select to move or copy",
+                checked: "{picked}",
+                onchange: move |evt| {
+                    if evt.value == "true" {
+                        selected.write().insert(uuid);
+                    } else {
+                        selected.write().shift_remove(&uuid);
+                    }
+                },
+            }
+            button {
+                onclick: move |_| {
+                    replying_to.set(Some(uuid));
+                    eval(&format!(r#"document.getElementById("{input_id}").focus();"#)).unwrap();
+                },
+                "Reply"
+            }
+            if in_thread && !following {
+                rsx! {
+                    button {
+                        onclick: move |_| thread.set(Some(uuid)),
+                        "{replies_label}"
+                    }
+                }
+            }
+            button {
+                title: "Start a new chat with the messages up to here",
+                onclick: move |_| on_branch.call(uuid),
+                "Branch from here"
+            }
+            button {
+                title: "Tag the emotions in this message",
+                onclick: move |_| tagging.set(!*tagging.read()),
+                "Feelings"
+            }
+        }
+        if *tagging.read() {
+            rsx! { EmotionPicker { uuid: uuid, messages: *messages } }
+        }
+    })
+}
+
+/// Tags a message with emotions from the wheel or custom ones, each at an intensity
+#[component]
+fn EmotionPicker(cx: Scope, uuid: Uuid, messages: Signal<Messages>) -> Element {
+    let chats = AppState::chats(cx);
+    let uuid = *uuid;
+    let family = use_signal(cx, || None::<&'static str>);
+    let intensity = use_signal(cx, || 3u8);
+    let custom = use_signal(cx, String::new);
+    // With how they're shown
+    let tags: Vec<(String, String)> = messages
+        .read()
+        .get(&uuid)
+        .map(|msg| msg.emotions.iter().map(|tag| (tag.name.clone(), format!("{} {}", tag.name, tag.intensity))).collect())
+        .unwrap_or_default();
+    let chosen = *intensity.read();
+    let levels: Vec<(u8, bool)> = INTENSITIES.map(|level| (level, level == chosen)).collect();
+    let ring: Vec<&'static str> = EMOTION_WHEEL
+        .iter()
+        .find(|(core, _)| Some(*core) == *family.read())
+        .map(|(core, ring)| std::iter::once(*core).chain(ring.iter().copied()).collect())
+        .unwrap_or_default();
+
+    let tag = move |name: &str| {
+        if let Some(msg) = messages.write().get_mut(&uuid) {
+            msg.tag_emotion(name, *intensity.read());
+        }
+        chats.read().save_active();
+    };
+    let untag = move |name: &str| {
+        if let Some(msg) = messages.write().get_mut(&uuid) {
+            msg.untag_emotion(name);
+        }
+        chats.read().save_active();
+    };
+    let add_custom = move || {
+        let name = custom.read().clone();
+        tag(&name);
+        custom.set(String::new());
+    };
+
+    cx.render(rsx! {
+        div { class: "flex flex-col gap-1 p-2 rounded-lg bg-panel text-xs text-left",
+            div { class: "flex flex-wrap gap-1",
+                for (core, _) in EMOTION_WHEEL {
+                    button {
+                        class: "rounded-full px-2 text-white",
+                        style: "background-color: {emotion_colour(core)};",
+                        onclick: move |_| family.set(Some(core)),
+                        "{core}"
+                    }
+                }
+            }
+            if !ring.is_empty() {
+                rsx! {
+                    div { class: "flex flex-wrap gap-1",
+                        for emotion in ring {
+                            button {
+                                class: "bg-raised rounded-full px-2",
+                                onclick: move |_| tag(emotion),
+                                "{emotion}"
+                            }
+                        }
+                    }
+                }
+            }
+            div { class: "flex gap-2 items-center",
+                "Intensity"
+                for (level, checked) in levels {
+                    label { class: "flex gap-1 items-center",
+                        input {
+                            r#type: "radio",
+                            name: "intensity_{uuid}",
+                            checked: "{checked}",
+                            onchange: move |_| intensity.set(level),
+                        }
+                        "{level}"
+                    }
+                }
+            }
+            div { class: "flex gap-2",
+                input {
+                    class: "bg-surface rounded-lg px-2 min-w-0",
+                    placeholder: "Your own word for it",
+                    value: "{custom.read()}",
+                    oninput: move |evt| custom.set(evt.value.clone()),
+                    onkeydown: keep_typing,
+                    onkeyup: move |evt| {
+                        if evt.key() == Key::Enter {
+                            add_custom();
+                        }
+                    },
+                }
+                button { class: "bg-raised px-2 rounded-lg", onclick: move |_| add_custom(), "Add" }
+            }
+            div { class: "flex flex-wrap gap-1",
+                for (name, label) in tags {
+                    span { class: "bg-raised rounded-full px-2",
+                        "{label} "
+                        button {
+                            title: "Remove this feeling",
+                            onclick: move |_| untag(&name),
+                            "×"
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Moves or copies the selected messages to another chat
+#[component]
+fn SelectionBar(cx: Scope, chat: Chat, selected: Signal<IndexSet<Uuid>>) -> Element {
+    let chats = AppState::chats(cx);
+    let target = use_state(cx, || None::<Uuid>);
+    let count = selected.read().len();
+    let others: Vec<(Uuid, String)> = chats
+        .read()
+        .chats()
+        .filter(|other| *other != chat)
+        .map(|other| (*other.uuid(), other.name.read().clone()))
+        .collect();
+    let transfer = move |moving: bool| {
+        let Some(to) = target.get().and_then(|uuid| chats.read().get(&uuid).copied()) else {
+            return;
+        };
+        let uuids = selected.read().clone();
+        let msgs = if moving { chat.take_messages(&uuids) } else { chat.selected_messages(&uuids) };
+        to.receive(if moving { msgs } else { copy_messages(&msgs) });
+        to.save();
+        chat.save();
+        selected.set(IndexSet::new());
+    };
+    let disabled = target.get().is_none();
+
+    cx.render(rsx! {
+        div { class: "absolute top-2 inset-x-2 flex gap-2 items-center p-2 rounded-xl bg-panel shadow text-sm",
+            "{count} selected"
+            select {
+                class: "bg-surface rounded-lg min-w-0",
+                onchange: move |evt| target.set(Uuid::parse_str(&evt.value).ok()),
+                option { value: "", "Choose a chat" }
+                for (uuid, name) in others {
+                    option { value: "{uuid}", "{name}" }
+                }
+            }
+            button { class: "bg-raised px-2 rounded-lg", disabled: disabled, onclick: move |_| transfer(true), "Move" }
+            button { class: "bg-raised px-2 rounded-lg", disabled: disabled, onclick: move |_| transfer(false), "Copy" }
+            button { class: "ml-auto px-2", onclick: move |_| selected.set(IndexSet::new()), "Clear" }
+        }
+    })
+}
+
+/// The 8 Cs rated at the start and end of the chat, each can be taken or retaken
+#[component]
+fn CheckInBar(cx: Scope, chat: Chat) -> Element {
+    let chat = *chat;
+    let check_ins = *chat.check_ins.read();
+    let editing = use_signal(cx, || None::<Moment>);
+    let draft = use_signal(cx, CheckIn::default);
+
+    let open = move |moment: Moment| {
+        // Retaking starts from the old ratings, the end from where the start left off
+        let check_ins = *chat.check_ins.read();
+        let from = check_ins.get(moment).or(check_ins.start.as_ref()).copied().unwrap_or_default();
+        draft.set(CheckIn { taken: 0, ..from });
+        editing.set(Some(moment));
+    };
+
+    let editing_moment = *editing.read();
+    let Some(moment) = editing_moment else {
+        // With the average once taken
+        let labels = Moment::ALL.map(|moment| match check_ins.get(moment) {
+            Some(check_in) => (moment, format!("{} check-in {:.1}", moment.name(), check_in.average())),
+            None => (moment, format!("{} check-in", moment.name())),
+        });
+        return cx.render(rsx! {
+            div { class: "flex gap-2 items-center text-xs text-left px-2 pb-1",
+                for (moment, label) in labels {
+                    button {
+                        class: "bg-raised px-2 rounded-lg",
+                        title: "Rate the 8 Cs",
+                        onclick: move |_| open(moment),
+                        "{label}"
+                    }
+                }
+            }
+        });
+    };
+    let current = *draft.read();
+
+    cx.render(rsx! {
+        div { class: "flex flex-col gap-1 text-xs text-left px-2 pb-1",
+            b { "{moment.name()} check-in, how much of each is there right now?" }
+            for quality in Quality::ALL {
+                label { class: "flex gap-2 items-center",
+                    span { class: "w-28", "{quality.name()}" }
+                    input {
+                        class: "flex-1",
+                        r#type: "range",
+                        min: "{RATINGS.start()}",
+                        max: "{RATINGS.end()}",
+                        value: "{current.rating(quality)}",
+                        oninput: move |evt| {
+                            if let Ok(rating) = evt.value.parse::<u8>() {
+                                draft.write().rate(quality, rating);
                             }
+                        },
+                    }
+                    span { class: "w-6", "{current.rating(quality)}" }
+                }
+            }
+            div { class: "flex gap-2",
+                button {
+                    class: "bg-accent text-on-accent px-2 rounded-lg",
+                    onclick: move |_| {
+                        let check_in = CheckIn { taken: CheckIn::now().taken, ..*draft.read() };
+                        chat.check_ins.write().set(moment, Some(check_in));
+                        chat.save();
+                        editing.set(None);
+                    },
+                    "Save"
+                }
+                if check_ins.get(moment).is_some() {
+                    rsx! {
+                        button {
+                            class: "bg-raised px-2 rounded-lg",
+                            onclick: move |_| {
+                                chat.check_ins.write().set(moment, None);
+                                chat.save();
+                                editing.set(None);
+                            },
+                            "Remove"
                         }
                     }
                 }
+                button {
+                    class: "bg-raised px-2 rounded-lg",
+                    onclick: move |_| editing.set(None),
+                    "Cancel"
+                }
+            }
+        }
+    })
+}
+
+/// Where a guided session is up to, or a way to start one
+#[component]
+fn GuideBar(cx: Scope, chat: Chat) -> Element {
+    let personas = AppState::personas(cx);
+    let picking = use_state(cx, || false);
+    let chat = *chat;
+    let session = chat.session.read().clone();
+
+    let Some(session) = session else {
+        let guides = AppState::guides(cx).read().all();
+        return cx.render(rsx! {
+            div { class: "flex gap-2 flex-wrap items-center text-xs text-left px-2 pb-1",
+                button {
+                    class: "bg-raised px-2 rounded-lg",
+                    title: "Walk through a guided session",
+                    onclick: move |_| picking.set(!**picking),
+                    "Guide"
+                }
+                if **picking {
+                    rsx! {
+                        guides.into_iter().map(|guide| {
+                            let name = guide.name.clone();
+                            let description = guide.description.clone();
+                            rsx! {
+                                button {
+                                    key: "{name}",
+                                    class: "bg-raised px-2 rounded-lg",
+                                    title: "{description}",
+                                    onclick: move |_| {
+                                        picking.set(false);
+                                        chat.start_guide(guide.clone(), &personas.read());
+                                    },
+                                    "{name}"
+                                }
+                            }
+                        })
+                    }
+                }
+            }
+        });
+    };
+    let Some(step) = session.current().cloned() else {
+        return cx.render(rsx! { "" });
+    };
+    let position = format!("{} of {}", session.step + 1, session.guide.steps.len());
+    let next = if session.is_last_step() { "Finish" } else { "Next step" };
+
+    cx.render(rsx! {
+        div { class: "flex gap-2 items-center text-xs text-left px-2 pb-1",
+            span { class: "truncate",
+                "{session.guide.name}, {position}: "
+                b { "{step.title}" }
+                if !step.answer_as.is_empty() {
+                    rsx! { ", answer as {step.answer_as}" }
+                }
+            }
+            button {
+                class: "ml-auto bg-raised px-2 rounded-lg",
+                onclick: move |_| {
+                    chat.next_guide_step(&personas.read());
+                },
+                "{next}"
+            }
+            button {
+                class: "bg-raised px-2 rounded-lg",
+                onclick: move |_| chat.end_guide(),
+                "End"
+            }
+        }
+    })
+}
+
+/// What the message being written will answer, if anything
+#[component]
+fn ReplyingTo(cx: Scope, messages: Signal<Messages>, replying_to: Signal<Option<Uuid>>) -> Element {
+    let personas = AppState::personas(cx);
+    let Some(parent) = replying_to.read().and_then(|parent| messages.read().get(&parent).cloned()) else {
+        return cx.render(rsx! { "" });
+    };
+
+    cx.render(rsx! {
+        div { class: "flex gap-2 items-center text-xs text-left px-2 pb-1",
+            span { class: "truncate", "Replying to {parent.speaker(&personas.read())}: {parent.preview(80)}" }
+            button {
+                class: "ml-auto bg-raised px-2 rounded-lg",
+                onclick: move |_| replying_to.set(None),
+                "Cancel"
             }
         }
     })
@@ -177,8 +721,8 @@ fn Message (cx: Scope, message: String, colour: Rgb) -> Element {
     let colour = *colour;
     cx.render(rsx!{
         div {
-            class: "rounded-lg px-2 py-1 w-fit text-left",
-            style: "{Colour::BgColour(colour)} {text_colour_from_bg(colour)}",
+            class: "bubble rounded-lg px-2 py-1 w-fit text-left",
+            style: "{bubble_style(colour)}",
             onmounted: move |cx2| {
                 cx2.inner().scroll_to(ScrollBehavior::Smooth);
             },
@@ -187,71 +731,205 @@ fn Message (cx: Scope, message: String, colour: Rgb) -> Element {
     })
 }
 
+/// What the popup above the message input offers to complete
+#[derive(Clone, Copy, PartialEq)]
+enum Suggestion {
+    Persona(Uuid),
+    /// A slash command, by name
+    Command(&'static str),
+}
+
 #[component]
-fn MessageInput<'a>(cx: Scope, id: &'a str, current_message: Signal<String>, active_persona: Signal<Uuid>, added_personas: Signal<IndexSet<Uuid>>, on_send: EventHandler<'a, ()>) -> Element {
+fn MessageInput<'a>(cx: Scope, id: &'a str, current_message: Signal<String>, active_persona: Signal<Uuid>, added_personas: Signal<IndexSet<Uuid>>, status: Signal<String>, kind: Signal<MessageKind>, on_send: EventHandler<'a, ()>) -> Element {
     let personas = AppState::personas(cx);
+    let settings = AppState::settings(cx);
+    let theme = settings.read().theme;
+    let registry = use_context::<SlashRegistry>(cx).expect("the layout provides the slash commands");
+    // Phones send Enter as a newline in the input rather than a key press
+    let send_on_newline = settings.read().keymap.shortcut(Action::Send) == Some(&Shortcut::key("Enter"));
     let eval = use_eval(cx);
+    let suggestion_selected = use_signal(cx, || 0usize);
+    let suggestions_dismissed = use_signal(cx, || false);
+
+    // An `@` at the start pops up the personas the message could be addressed to,
+    // a `/` the commands it could be
+    let message = current_message.read().clone();
+    let suggestions: Vec<Suggestion> = match (message.strip_prefix('@'), parse_slash(&message)) {
+        _ if *suggestions_dismissed.read() || message.contains('\n') => Vec::new(),
+        (Some(query), _) if personas.read().parse_mention(&message).is_none() => {
+            personas.read().mention_candidates(query).into_iter().take(6).map(Suggestion::Persona).collect()
+        }
+        (_, Some((name, ""))) if !message.ends_with(char::is_whitespace) => {
+            registry.completions(name).into_iter().take(6).map(|command| Suggestion::Command(command.name)).collect()
+        }
+        _ => Vec::new(),
+    };
+    // Help for the command being written once its name is complete
+    let command_help = match parse_slash(&message).and_then(|(name, _)| registry.get(name)) {
+        Some(command) if suggestions.is_empty() => Some(format!("{} – {}", command.synopsis(), command.help)),
+        _ => None,
+    };
+    let selected = (*suggestion_selected.read()).min(suggestions.len().saturating_sub(1));
+    let popup = suggestions.clone();
+    let complete = move |suggestion: Suggestion| {
+        let text = match suggestion {
+            Suggestion::Persona(uuid) => {
+                let name = personas.read().get(&uuid).map(|persona| persona.name.clone()).unwrap_or_default();
+                format!("@{name} ")
+            }
+            Suggestion::Command(name) => format!("/{name} "),
+        };
+        let js = format!(r#"
+            el = document.getElementById("{id}");
+            el.value = {};
+            el.focus();
+        "#, serde_json::to_string(&text).unwrap());
+        current_message.set(text);
+        eval(&js).unwrap();
+    };
+    // Clears the element, or puts back a command that failed, for correct height resize
+    let sync_value = move || {
+        let value = serde_json::to_string(&*current_message.read()).unwrap();
+        eval(&format!(r#"document.getElementById("{id}").value = {value};"#)).unwrap();
+    };
+
     // let id = use_signal(cx, move || id);
     cx.render(rsx!{
-        textarea {
-            id: *id,
-            class: "flex p-2 max-h-32 h-auto w-full rounded-xl bg-gray-200 outline-none hover:outline-none",
-            rows: 1,
-            placeholder: "Add message ...",
-            onmounted: move |cx2| {
-                cx2.inner().set_focus(true);
-            },
-            oninput: move |mut evt| {
-                if evt.value.ends_with('\n') {
-                    on_send.call(());
-                    // Clear the element value for correct height resize
-                    let js = format!(r#"document.getElementById("{id}").value = "";"#);
-                    eval(&js).unwrap();
-                } else {
-                    current_message.set(evt.value.clone());
+        div { class: "relative w-full",
+            if !suggestions.is_empty() {
+                rsx! {
+                    div { class: "absolute bottom-full mb-1 flex flex-col w-80 p-1 rounded-xl bg-surface border shadow text-left",
+                        popup.iter().enumerate().map(|(i, suggestion)| {
+                            let suggestion = *suggestion;
+                            let style = if i == selected { "bg-raised" } else { "" };
+                            match suggestion {
+                                Suggestion::Persona(uuid) => {
+                                    let persona = personas.read().get(&uuid).cloned().unwrap_or_default();
+                                    let note = if added_personas.read().contains(&uuid) { "" } else { "add to chat" };
+                                    rsx! {
+                                        button {
+                                            key: "{uuid}",
+                                            class: "flex items-center gap-2 px-2 rounded-lg {style}",
+                                            onclick: move |_| complete(suggestion),
+                                            PersonaIcon {
+                                                colour: theme.persona_colour(persona.colour),
+                                                marker: theme.markers.then(|| personas.read().marker(&uuid)),
+                                            }
+                                            span { "{persona.name}" }
+                                            span { class: "text-xs ml-auto", "{note}" }
+                                        }
+                                    }
+                                }
+                                Suggestion::Command(name) => {
+                                    let command = registry.get(name).copied();
+                                    let synopsis = command.map(|command| command.synopsis()).unwrap_or_default();
+                                    let help = command.map_or("", |command| command.help);
+                                    rsx! {
+                                        button {
+                                            key: "{name}",
+                                            class: "flex items-center gap-2 px-2 rounded-lg {style}",
+                                            onclick: move |_| complete(suggestion),
+                                            span { class: "font-mono", "{synopsis}" }
+                                            span { class: "text-xs ml-auto", "{help}" }
+                                        }
+                                    }
+                                }
+                            }
+                        })
+                    }
                 }
-                let js = format!(r#"
-                    el = document.getElementById("{id}");
-                    el.style.height = "auto";
-                    el.style.height = el.scrollHeight + "px";
-                "#);
+            }
+            if let Some(help) = command_help {
+                rsx! { p { class: "text-xs text-left px-2 font-mono", "{help}" } }
+            }
+            if !status.read().is_empty() {
+                rsx! { p { class: "text-xs text-left px-2 whitespace-pre-line", "{status}" } }
+            }
+            div { class: "flex gap-1 pb-1 text-xs",
+                for option in MessageKind::ALL {
+                    button {
+                        class: if *kind.read() == option { "px-2 rounded-lg bg-accent text-on-accent" } else { "px-2 rounded-lg bg-raised" },
+                        onclick: move |_| kind.set(option),
+                        "{option.name()}"
+                    }
+                }
+            }
+            textarea {
+                id: *id,
+                class: "flex p-2 max-h-32 h-auto w-full rounded-xl bg-panel outline-none hover:outline-none",
+                rows: 1,
+                placeholder: "Add message, or / for commands ...",
+                onmounted: move |cx2| {
+                    cx2.inner().set_focus(true);
+                },
+                oninput: move |mut evt| {
+                    if send_on_newline && evt.value.ends_with('\n') {
+                        on_send.call(());
+                        sync_value();
+                    } else {
+                        current_message.set(evt.value.clone());
+                        suggestions_dismissed.set(false);
+                        suggestion_selected.set(0);
+                        status.set(String::new());
+                    }
+                    let js = format!(r#"
+                        el = document.getElementById("{id}");
+                        el.style.height = "auto";
+                        el.style.height = el.scrollHeight + "px";
+                    "#);
 
-                eval(&js).unwrap();
-            },
-            prevent_default: "onkeydown onkeyup",
-            onkeyup: move |evt| {
-                let persona_index = added_personas
-                    .read()
-                    .iter()
-                    .position(|r| *active_persona.read() == *r)
-                    .unwrap();
-                if evt.key() == Key::Enter && !current_message.read().is_empty() {
-                    on_send.call(());
-                    let js = format!(r#"document.getElementById("{id}").value = "";"#);
                     eval(&js).unwrap();
-                } else if evt.modifiers() == Modifiers::SHIFT && evt.key() == Key::Tab {
-                    if persona_index > 0 {
-                        active_persona
-                            .set(*added_personas.read().get_index(persona_index - 1).unwrap());
-                    } else {
-                        active_persona
-                            .set(
-                                *added_personas
-                                    .read()
-                                    .get_index(added_personas.with(|personas| personas.len() - 1))
-                                    .unwrap(),
-                            );
+                },
+                onkeydown: move |evt| {
+                    let key = evt.key();
+                    if !suggestions.is_empty() {
+                        let count = suggestions.len();
+                        // Enter on a command that's already typed out runs it
+                        let typed = matches!(suggestions[selected], Suggestion::Command(name) if *current_message.read() == format!("/{name}"));
+                        match key {
+                            Key::ArrowDown => return suggestion_selected.set((selected + 1) % count),
+                            Key::ArrowUp => return suggestion_selected.set((selected + count - 1) % count),
+                            Key::Enter if typed => {}
+                            Key::Tab | Key::Enter => return complete(suggestions[selected]),
+                            Key::Escape => return suggestions_dismissed.set(true),
+                            _ => {}
+                        }
                     }
-                } else if evt.key() == Key::Tab {
-                    if persona_index < added_personas.with(IndexSet::len) - 1 {
-                        active_persona
-                            .set(*added_personas.read().get_index(persona_index + 1).unwrap());
-                    } else {
-                        active_persona.set(*added_personas.read().get_index(0).unwrap());
+                    match settings.read().keymap.action(&key, evt.modifiers()) {
+                        Some(Action::Send) => {
+                            if !current_message.read().is_empty() {
+                                on_send.call(());
+                                sync_value();
+                            }
+                        }
+                        Some(Action::Newline) => {
+                            // Goes in at the caret, which only the browser knows
+                            let js = format!(r#"
+                                el = document.getElementById("{id}");
+                                el.setRangeText("\n", el.selectionStart, el.selectionEnd, "end");
+                                el.style.height = "auto";
+                                el.style.height = el.scrollHeight + "px";
+                                dioxus.send(el.value);
+                            "#);
+                            if let Ok(inserted) = eval(&js) {
+                                cx.spawn(async move {
+                                    if let Ok(serde_json::Value::String(value)) = inserted.recv().await {
+                                        current_message.set(value);
+                                    }
+                                });
+                            }
+                        }
+                        Some(action @ (Action::NextPersona | Action::PreviousPersona)) => {
+                            let backwards = action == Action::PreviousPersona;
+                            let next = cycle_persona(&added_personas.read(), &active_persona.read(), backwards);
+                            active_persona.set(next);
+                        }
+                        _ if Shortcut::from_event(&key, evt.modifiers()).types() => evt.stop_propagation(),
+                        _ => {}
                     }
-                }
-            },
-            value: "{current_message}"
+                },
+                value: "{current_message}"
+            }
         }
     })
 }
@@ -276,7 +954,7 @@ fn BottomBar<'a>(cx: Scope, add_persona_id: &'a str, input_id: &'a str, active_p
                 }
             }
             button {
-                class: "px-4 py-1 text-sm text-gray-900 font-semibold rounded-xl hover:text-gray-900 hover:bg-gray-200 hover:border-transparent focus:outline-none focus:ring-2 focus:ring-gray-100",
+                class: "px-4 py-1 text-sm text-ink font-semibold rounded-xl hover:bg-panel hover:border-transparent focus:outline-none focus:ring-2 focus:ring-raised",
                 onclick: move |_| {
                     on_send.call(());
                     let js = format!(r#"document.getElementById("{input_id}").focus();"#);
@@ -292,6 +970,7 @@ fn BottomBar<'a>(cx: Scope, add_persona_id: &'a str, input_id: &'a str, active_p
 #[component]
 fn PersonaSelect<'a>(cx: Scope, input_id: &'a str, active_persona: Signal<Uuid>, added_personas: Signal<IndexSet<Uuid>>) -> Element {
     let personas = AppState::personas(cx);
+    let theme = AppState::settings(cx).read().theme;
     let eval = use_eval(cx);
     cx.render(rsx!{
         added_personas.read().iter().map(|uuid| {
@@ -316,7 +995,8 @@ fn PersonaSelect<'a>(cx: Scope, input_id: &'a str, active_persona: Signal<Uuid>,
                                 rsx!{
                                     PersonaButton {
                                     name: persona.name.clone(),
-                                    colour: persona.colour,
+                                    colour: theme.persona_colour(persona.colour),
+                                    marker: theme.markers.then(|| personas.read().marker(&uuid)),
                                     onclick: move |_| {
                                         active_persona.set(uuid);
                                         let js = format!(r#"document.getElementById("{input_id}").focus();"#);
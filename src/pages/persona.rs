@@ -0,0 +1,84 @@
+use crate::components::*;
+use crate::data::*;
+use dioxus::prelude::*;
+use dioxus_signals::*;
+use uuid::Uuid;
+
+/// How much a persona has said and the feelings tagged on it, grouped by where they sit on the wheel
+#[component]
+pub fn PersonaPage(cx: Scope, persona: Uuid) -> Element {
+    let personas = AppState::personas(cx);
+    let chats = AppState::chats(cx);
+    let theme = AppState::settings(cx).read().theme;
+    let Some(details) = personas.read().get(persona).cloned() else {
+        return cx.render(rsx! { p { "This persona has been removed" } });
+    };
+
+    let mut message_count = 0;
+    let mut chat_count = 0;
+    let mut msgs = Vec::new();
+    for chat in chats.read().chats() {
+        let said: Vec<Message> = chat
+            .messages
+            .read()
+            .msgs
+            .iter()
+            .filter(|msg| msg.persona == *persona && msg.kind.has_speaker())
+            .cloned()
+            .collect();
+        if !said.is_empty() {
+            chat_count += 1;
+            message_count += said.len();
+        }
+        msgs.extend(said);
+    }
+    let summary = emotion_summary(&msgs, *persona);
+    let tagged: usize = msgs.iter().filter(|msg| !msg.emotions.is_empty()).count();
+    let families: Vec<(&'static str, Vec<(String, usize, String)>)> = EMOTION_WHEEL
+        .iter()
+        .map(|(core, _)| *core)
+        .chain(std::iter::once(CUSTOM_FAMILY))
+        .map(|family| {
+            let emotions: Vec<(String, usize, String)> = summary
+                .iter()
+                .filter(|emotion| emotion.family == family)
+                .map(|emotion| (emotion.name.clone(), emotion.count, format!("{:.1}", emotion.average_intensity)))
+                .collect();
+            (family, emotions)
+        })
+        .filter(|(_, emotions)| !emotions.is_empty())
+        .collect();
+
+    cx.render(rsx! {
+        div { class: "flex flex-col gap-4 text-left h-full overflow-y-auto",
+            div { class: "flex items-center gap-2",
+                PersonaIcon {
+                    colour: theme.persona_colour(details.colour),
+                    marker: theme.markers.then(|| personas.read().marker(persona)),
+                }
+                h2 { class: "text-xl font-bold", "{details.name}" }
+                button {
+                    class: "ml-auto bg-raised px-2 rounded-lg",
+                    onclick: move |_| AppState::page(cx).set(crate::pages::Page::Chat),
+                    "Back to chat"
+                }
+            }
+            p { "{message_count} messages across {chat_count} chats, {tagged} tagged with feelings" }
+            if families.is_empty() {
+                rsx! { p { "No feelings tagged yet, add them from the Feelings button under a message" } }
+            }
+            for (family, emotions) in families {
+                div { class: "flex flex-col gap-1",
+                    h3 { class: "font-bold", style: "color: {emotion_colour(family)};", "{family}" }
+                    for (name, count, intensity) in emotions {
+                        div { class: "flex gap-2 text-sm",
+                            span { class: "w-32", "{name}" }
+                            span { "{count} times" }
+                            span { class: "opacity-70", "intensity {intensity} of 5" }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,472 @@
+use std::str::FromStr;
+
+use crate::colours::{theme, vision, Colour, Rgb};
+use crate::components::*;
+use crate::data::*;
+use dioxus::prelude::*;
+use dioxus_signals::*;
+use uuid::Uuid;
+
+const SELECTED: &str = "bg-accent text-on-accent px-2 rounded-lg";
+const UNSELECTED: &str = "bg-raised px-2 rounded-lg";
+
+#[component]
+pub fn SettingsPage(cx: Scope) -> Element {
+    let settings = AppState::settings(cx);
+    let current = settings.read().clone();
+
+    cx.render(rsx! {
+        div { class: "flex flex-col gap-4 text-left h-full overflow-y-auto",
+            ShortcutSettings {}
+            NewChatSettings {}
+            TemplateSettings {}
+            GuideSettings {}
+            section { class: "flex flex-col gap-2",
+                h2 { class: "text-xl font-bold", "Text" }
+                label { class: "flex gap-2 items-center",
+                    "Font size"
+                    input {
+                        r#type: "range",
+                        min: "{FONT_SIZES.start()}",
+                        max: "{FONT_SIZES.end()}",
+                        value: "{current.font_size}",
+                        oninput: move |evt| {
+                            if let Ok(size) = evt.value.parse::<u8>() {
+                                settings.write().font_size = size.clamp(*FONT_SIZES.start(), *FONT_SIZES.end());
+                            }
+                        },
+                    }
+                    "{current.font_size}px"
+                }
+            }
+            ThemeSettings {}
+        }
+    })
+}
+
+/// Every action with its shortcut, click one then press the new keys to rebind it
+#[component]
+fn ShortcutSettings(cx: Scope) -> Element {
+    let settings = AppState::settings(cx);
+    let keymap = settings.read().keymap.clone();
+    let capturing = use_state(cx, || None::<Action>);
+
+    cx.render(rsx! {
+        section { class: "flex flex-col gap-2",
+            h2 { class: "text-xl font-bold", "Shortcuts" }
+            Action::all().into_iter().map(|action| {
+                let recording = *capturing.get() == Some(action);
+                let label = match keymap.shortcut(action) {
+                    _ if recording => "Press keys, Esc to cancel, Backspace to clear".to_string(),
+                    Some(shortcut) => shortcut.to_string(),
+                    None => "Not set".to_string(),
+                };
+                rsx! {
+                    div { key: "{action:?}", class: "flex gap-2 items-center",
+                        span { class: "w-48", "{action.description()}" }
+                        button {
+                            class: if recording { SELECTED } else { UNSELECTED },
+                            onclick: move |_| capturing.set(Some(action)),
+                            onkeydown: move |evt| {
+                                if *capturing.get() != Some(action) {
+                                    return;
+                                }
+                                // The shortcut being recorded shouldn't also do its old job
+                                evt.stop_propagation();
+                                let shortcut = Shortcut::from_event(&evt.key(), evt.modifiers());
+                                if shortcut.is_modifier() {
+                                    return;
+                                }
+                                if shortcut == Shortcut::key("Backspace") {
+                                    settings.write().keymap.unbind(action);
+                                } else if shortcut != Shortcut::key("Escape") {
+                                    settings.write().keymap.bind(action, shortcut);
+                                }
+                                capturing.set(None);
+                            },
+                            "{label}"
+                        }
+                        for other in keymap.conflicts(action) {
+                            span { class: "text-xs text-red-700", "Also {other.description()}" }
+                        }
+                    }
+                }
+            })
+            button {
+                class: UNSELECTED,
+                onclick: move |_| settings.write().keymap = Keymap::default(),
+                "Reset shortcuts"
+            }
+        }
+    })
+}
+
+/// How new chats are named and who's in them
+#[component]
+fn NewChatSettings(cx: Scope) -> Element {
+    let settings = AppState::settings(cx);
+    let personas = AppState::personas(cx);
+    let current = settings.read().clone();
+    let format_error = !valid_date_format(&current.date_format);
+    let preview = current.chat_name();
+
+    cx.render(rsx! {
+        section { class: "flex flex-col gap-2",
+            h2 { class: "text-xl font-bold", "New chats" }
+            label { class: "flex flex-col gap-0",
+                "Name format"
+                input {
+                    class: "bg-panel px-2 rounded-lg",
+                    value: "{current.date_format}",
+                    onkeydown: keep_typing,
+                    oninput: move |evt| settings.write().date_format = evt.value.clone(),
+                }
+                if format_error {
+                    rsx! { p { class: "text-xs text-red-700", "That isn't a valid date format, new chats use the default" } }
+                }
+                p { class: "text-xs", "New chats will be called \"{preview}\"" }
+            }
+            "Start with"
+            div { class: "flex gap-4 flex-wrap",
+                personas.read().iter().map(|(uuid, persona)| {
+                    let uuid = *uuid;
+                    let checked = current.default_personas.contains(&uuid)
+                        || current.default_personas.is_empty() && personas.read().get_index_of(&uuid) == Some(0);
+                    rsx! {
+                        label { key: "{uuid}", class: "flex gap-1 items-center",
+                            input {
+                                r#type: "checkbox",
+                                checked: "{checked}",
+                                onchange: move |evt| {
+                                    let mut settings = settings.write();
+                                    if settings.default_personas.is_empty() {
+                                        settings.default_personas = settings.starting_personas(&personas.read());
+                                    }
+                                    if evt.value == "true" {
+                                        settings.default_personas.insert(uuid);
+                                    } else {
+                                        settings.default_personas.shift_remove(&uuid);
+                                    }
+                                },
+                            }
+                            PersonaIcon { colour: persona.colour }
+                            "{persona.name}"
+                        }
+                    }
+                })
+            }
+        }
+    })
+}
+
+/// Changes the template with `uuid`, if it's still there
+fn edit_template(settings: Signal<Settings>, uuid: Uuid, edit: impl FnOnce(&mut ChatTemplate)) {
+    if let Some(template) = settings.write().templates.iter_mut().find(|template| template.uuid == uuid) {
+        edit(template);
+    }
+}
+
+/// Ready made ways to start a chat, offered by "New Chat"
+#[component]
+fn TemplateSettings(cx: Scope) -> Element {
+    let settings = AppState::settings(cx);
+    let templates = settings.read().templates.clone();
+
+    cx.render(rsx! {
+        section { class: "flex flex-col gap-2",
+            h2 { class: "text-xl font-bold", "Chat templates" }
+            for template in templates {
+                TemplateEditor { key: "{template.uuid}", template: template }
+            }
+            button {
+                class: UNSELECTED,
+                onclick: move |_| {
+                    let mut settings = settings.write();
+                    let name = format!("Template {}", settings.templates.len() + 1);
+                    settings.templates.push(ChatTemplate::new(name));
+                },
+                "Add template"
+            }
+        }
+    })
+}
+
+#[component]
+fn TemplateEditor(cx: Scope, template: ChatTemplate) -> Element {
+    let settings = AppState::settings(cx);
+    let personas = AppState::personas(cx);
+    let uuid = template.uuid;
+    let format_error = !valid_date_format(&template.name_format);
+    let preview = template.chat_name();
+    let starting = template.starting_personas(&personas.read());
+    let active = template.starting_persona.filter(|persona| starting.contains(persona)).unwrap_or(starting[0]);
+    let choices: Vec<(Uuid, String)> = starting
+        .iter()
+        .filter_map(|uuid| personas.read().get(uuid).map(|persona| (*uuid, persona.name.clone())))
+        .collect();
+
+    cx.render(rsx! {
+        div { class: "flex flex-col gap-2 bg-panel p-2 rounded-lg",
+            div { class: "flex gap-2",
+                input {
+                    class: "bg-raised px-2 rounded-lg flex-1 font-bold",
+                    value: "{template.name}",
+                    onkeydown: keep_typing,
+                    oninput: move |evt| edit_template(settings, uuid, |template| template.name = evt.value.clone()),
+                }
+                button {
+                    class: UNSELECTED,
+                    onclick: move |_| settings.write().templates.retain(|template| template.uuid != uuid),
+                    "Delete"
+                }
+            }
+            label { class: "flex flex-col gap-0",
+                "Name format"
+                input {
+                    class: "bg-raised px-2 rounded-lg",
+                    value: "{template.name_format}",
+                    onkeydown: keep_typing,
+                    oninput: move |evt| edit_template(settings, uuid, |template| template.name_format = evt.value.clone()),
+                }
+                if format_error {
+                    rsx! { p { class: "text-xs text-red-700", "That isn't a valid date format, the template's name and date are used" } }
+                }
+                p { class: "text-xs", "Chats will be called \"{preview}\"" }
+            }
+            "With"
+            div { class: "flex gap-4 flex-wrap",
+                personas.read().iter().map(|(persona_uuid, persona)| {
+                    let persona_uuid = *persona_uuid;
+                    let checked = starting.contains(&persona_uuid);
+                    rsx! {
+                        label { key: "{persona_uuid}", class: "flex gap-1 items-center",
+                            input {
+                                r#type: "checkbox",
+                                checked: "{checked}",
+                                onchange: move |evt| {
+                                    let personas = personas.read();
+                                    edit_template(settings, uuid, |template| {
+                                        if template.personas.is_empty() {
+                                            template.personas = template.starting_personas(&personas);
+                                        }
+                                        if evt.value == "true" {
+                                            template.personas.insert(persona_uuid);
+                                        } else {
+                                            template.personas.shift_remove(&persona_uuid);
+                                        }
+                                    });
+                                },
+                            }
+                            PersonaIcon { colour: persona.colour }
+                            "{persona.name}"
+                        }
+                    }
+                })
+            }
+            label { class: "flex gap-2 items-center",
+                "Speaking first"
+                select {
+                    class: "bg-raised px-2 rounded-lg",
+                    onchange: move |evt| {
+                        let persona = evt.value.parse::<Uuid>().ok();
+                        edit_template(settings, uuid, |template| template.starting_persona = persona);
+                    },
+                    for (persona_uuid, name) in choices {
+                        option { value: "{persona_uuid}", selected: persona_uuid == active, "{name}" }
+                    }
+                }
+            }
+            label { class: "flex flex-col gap-0",
+                "Opening narration"
+                textarea {
+                    class: "bg-raised px-2 rounded-lg",
+                    rows: "2",
+                    placeholder: "Posted to start the chat, leave empty for none",
+                    value: "{template.opening}",
+                    onkeydown: keep_typing,
+                    oninput: move |evt| edit_template(settings, uuid, |template| template.opening = evt.value.clone()),
+                }
+            }
+        }
+    })
+}
+
+/// The scripts for guided sessions, where new ones are imported
+#[component]
+fn GuideSettings(cx: Scope) -> Element {
+    let guides = AppState::guides(cx);
+    let status = use_state(cx, String::new);
+    let eval = use_eval(cx);
+    let builtin = Guides::builtin().len();
+    let all = guides.read().all();
+
+    cx.render(rsx! {
+        section { class: "flex flex-col gap-2",
+            h2 { class: "text-xl font-bold", "Guided sessions" }
+            p { class: "text-xs",
+                "Scripts are JSON with a name, a description and steps, each with a title, a prompt and who should answer. "
+                "Download one to see the format."
+            }
+            all.into_iter().enumerate().map(|(i, guide)| {
+                let name = guide.name.clone();
+                let description = guide.description.clone();
+                let steps = guide.steps.len();
+                let json = serde_json::to_string_pretty(&guide).unwrap_or_default();
+                let file = format!("{name}.json");
+                let eval = eval.clone();
+                rsx! {
+                    div { key: "{name}", class: "flex gap-2 items-center",
+                        span { class: "flex-1", title: "{description}", "{name}, {steps} steps" }
+                        button {
+                            class: UNSELECTED,
+                            onclick: move |_| {
+                                eval(&download_js(&file, "application/json", &json)).unwrap();
+                            },
+                            "Download"
+                        }
+                        if i >= builtin {
+                            rsx! {
+                                button {
+                                    class: UNSELECTED,
+                                    onclick: move |_| {
+                                        guides.write().remove(&guide.name);
+                                    },
+                                    "Remove"
+                                }
+                            }
+                        }
+                    }
+                }
+            })
+            label { class: "flex flex-col gap-0",
+                "Import scripts"
+                input {
+                    r#type: "file",
+                    accept: ".json",
+                    onchange: move |evt| {
+                        let status = status.clone();
+                        cx.spawn(async move {
+                            let Some(engine) = &evt.files else { return };
+                            for name in engine.files() {
+                                let Some(contents) = engine.read_file_to_string(&name).await else {
+                                    status.set(format!("Couldn't read {name}"));
+                                    return;
+                                };
+                                match Guide::parse(&contents) {
+                                    Ok(imported) => {
+                                        let count = imported.len();
+                                        guides.write().import(imported);
+                                        status.set(format!("Imported {count} from {name}"));
+                                    }
+                                    Err(e) => status.set(format!("Couldn't import {name}: {e}")),
+                                }
+                            }
+                        });
+                    }
+                }
+            }
+            p { class: "text-xs", "{status}" }
+        }
+    })
+}
+
+/// Light, dark or following the system, an accent colour and colour vision aids
+#[component]
+fn ThemeSettings(cx: Scope) -> Element {
+    let settings = AppState::settings(cx);
+    let current = settings.read().theme;
+    let accent = current.accent.unwrap_or(theme::ThemeColours::LIGHT.accent);
+
+    cx.render(rsx! {
+        section { class: "flex flex-col gap-2",
+            h2 { class: "text-xl font-bold", "Theme" }
+            div { class: "flex gap-2",
+                for mode in theme::ThemeMode::ALL {
+                    button {
+                        class: if current.mode == mode { SELECTED } else { UNSELECTED },
+                        onclick: move |_| settings.write().theme.mode = mode,
+                        "{mode.name()}"
+                    }
+                }
+            }
+            div { class: "flex gap-2 items-center",
+                "Accent colour"
+                input {
+                    r#type: "color",
+                    onchange: move |evt| {
+                        if let Ok(colour) = Rgb::from_str(&evt.value) {
+                            settings.write().theme.accent = Some(colour);
+                        }
+                    },
+                    value: "{accent}"
+                }
+                if current.accent.is_some() {
+                    rsx! {
+                        button {
+                            class: UNSELECTED,
+                            onclick: move |_| settings.write().theme.accent = None,
+                            "Reset"
+                        }
+                    }
+                }
+            }
+            h2 { class: "text-xl font-bold", "Colour vision" }
+            label { class: "flex gap-2 items-center",
+                input {
+                    r#type: "checkbox",
+                    checked: "{current.markers}",
+                    onchange: move |evt| settings.write().theme.markers = evt.value == "true",
+                }
+                "Show a shape with each persona's colour"
+            }
+            div { class: "flex gap-2",
+                "Preview as"
+                button {
+                    class: if current.simulate.is_none() { SELECTED } else { UNSELECTED },
+                    onclick: move |_| settings.write().theme.simulate = None,
+                    "Normal"
+                }
+                for deficiency in vision::Deficiency::ALL {
+                    button {
+                        class: if current.simulate == Some(deficiency) { SELECTED } else { UNSELECTED },
+                        onclick: move |_| settings.write().theme.simulate = Some(deficiency),
+                        "{deficiency.name()}"
+                    }
+                }
+            }
+            PaletteCheck {}
+        }
+    })
+}
+
+/// The persona palette as seen with each colour vision deficiency, flagging
+/// personas that can't be told apart
+#[component]
+fn PaletteCheck(cx: Scope) -> Element {
+    let personas = AppState::personas(cx);
+    let personas = personas.read();
+    let colours = personas.colours();
+    let name = |i: usize| personas.get_index(i).map_or("", |(_, persona)| persona.name.as_str());
+
+    cx.render(rsx! {
+        for deficiency in vision::Deficiency::ALL {
+            div { class: "flex flex-col gap-1",
+                div { class: "flex gap-1 items-center flex-wrap",
+                    span { class: "text-sm w-28", "{deficiency.name()}" }
+                    for (i, colour) in colours.iter().enumerate() {
+                        span {
+                            class: "w-5 h-5 rounded",
+                            title: "{name(i)}",
+                            style: "{Colour::BgColour(deficiency.simulate(*colour))}",
+                        }
+                    }
+                }
+                for (a, b) in deficiency.confusable_pairs(&colours) {
+                    p { class: "text-xs text-red-700",
+                        "{name(a)} and {name(b)} are hard to tell apart, try another colour or turn on shapes"
+                    }
+                }
+            }
+        }
+    })
+}
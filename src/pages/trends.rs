@@ -0,0 +1,165 @@
+use crate::data::*;
+use dioxus::prelude::*;
+use dioxus_signals::*;
+
+const SELECTED: &str = "bg-accent text-on-accent px-2 rounded-lg";
+const UNSELECTED: &str = "bg-raised px-2 rounded-lg";
+const WIDTH: f64 = 600.0;
+const HEIGHT: f64 = 240.0;
+const PADDING: f64 = 24.0;
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+/// How far back the chart goes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Span {
+    Weeks,
+    Months,
+    Year,
+    All,
+}
+
+impl Span {
+    const ALL: [Span; 4] = [Span::Weeks, Span::Months, Span::Year, Span::All];
+
+    fn name(self) -> &'static str {
+        match self {
+            Span::Weeks => "4 weeks",
+            Span::Months => "3 months",
+            Span::Year => "A year",
+            Span::All => "Everything",
+        }
+    }
+
+    fn days(self) -> Option<i64> {
+        match self {
+            Span::Weeks => Some(28),
+            Span::Months => Some(91),
+            Span::Year => Some(365),
+            Span::All => None,
+        }
+    }
+}
+
+/// Each C gets a hue of its own, spread around the wheel
+fn line_colour(quality: Quality) -> String {
+    let index = Quality::ALL.iter().position(|other| *other == quality).unwrap_or(0);
+    format!("hsl({}, 65%, 45%)", index * 45)
+}
+
+/// The 8 Cs check-ins across chats, charted over time
+#[component]
+pub fn TrendsPage(cx: Scope) -> Element {
+    let chats = AppState::chats(cx);
+    let moment = use_signal(cx, Moment::default);
+    let span = use_signal(cx, || Span::Months);
+    let hidden = use_signal(cx, Vec::<Quality>::new);
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let since = span.read().days().map_or(i64::MIN, |days| now - days * DAY_MILLIS);
+    let check_ins: Vec<CheckIns> = chats.read().chats().map(|chat| *chat.check_ins.read()).collect();
+    let points = trend(&check_ins, *moment.read(), since);
+    let average_ratings = averages(&points);
+
+    // Time runs left to right over the span, or from the first check-in when showing everything
+    let start = if since == i64::MIN { points.first().map_or(now, |point| point.taken) } else { since };
+    let x = |taken: i64| PADDING + (taken - start) as f64 / (now - start).max(1) as f64 * (WIDTH - 2.0 * PADDING);
+    let y = |rating: u8| HEIGHT - PADDING - (rating as f64 - 1.0) / 9.0 * (HEIGHT - 2.0 * PADDING);
+    let lines: Vec<(String, String)> = Quality::ALL
+        .into_iter()
+        .filter(|quality| !hidden.read().contains(quality))
+        .map(|quality| {
+            let index = Quality::ALL.iter().position(|other| *other == quality).unwrap_or(0);
+            let coords: Vec<String> = points
+                .iter()
+                .map(|point| format!("{:.1},{:.1}", x(point.taken), y(point.ratings[index])))
+                .collect();
+            (line_colour(quality), coords.join(" "))
+        })
+        .collect();
+    // With where their label sits
+    let gridlines: Vec<(u8, f64, f64)> = [1, 5, 10].into_iter().map(|rating| (rating, y(rating), y(rating) + 4.0)).collect();
+    let right = WIDTH - PADDING;
+    let legend: Vec<(Quality, String, bool)> = Quality::ALL
+        .into_iter()
+        .enumerate()
+        .map(|(i, quality)| {
+            let average = average_ratings.map_or(String::new(), |averages| format!("{:.1}", averages[i]));
+            (quality, average, hidden.read().contains(&quality))
+        })
+        .collect();
+    let count = points.len();
+
+    cx.render(rsx! {
+        div { class: "flex flex-col gap-4 text-left h-full overflow-y-auto",
+            h2 { class: "text-xl font-bold", "Self energy over time" }
+            div { class: "flex gap-2 flex-wrap",
+                for option in Moment::ALL {
+                    button {
+                        class: if *moment.read() == option { SELECTED } else { UNSELECTED },
+                        onclick: move |_| moment.set(option),
+                        "{option.name()} of sessions"
+                    }
+                }
+            }
+            div { class: "flex gap-2 flex-wrap",
+                for option in Span::ALL {
+                    button {
+                        class: if *span.read() == option { SELECTED } else { UNSELECTED },
+                        onclick: move |_| span.set(option),
+                        "{option.name()}"
+                    }
+                }
+            }
+            if count == 0 {
+                rsx! { p { "No check-ins in this time yet, take one from a chat at its start or end" } }
+            } else {
+                rsx! {
+                    svg {
+                        class: "w-full bg-panel rounded-lg",
+                        "viewBox": "0 0 {WIDTH} {HEIGHT}",
+                        for (rating, height, label_height) in gridlines {
+                            line {
+                                "x1": "{PADDING}",
+                                "x2": "{right}",
+                                "y1": "{height}",
+                                "y2": "{height}",
+                                "stroke": "currentColor",
+                                "stroke-opacity": "0.2",
+                            }
+                            text { "x": "4", "y": "{label_height}", "font-size": "10", "fill": "currentColor", "{rating}" }
+                        }
+                        for (colour, coords) in lines {
+                            polyline {
+                                "points": "{coords}",
+                                "fill": "none",
+                                "stroke": "{colour}",
+                                "stroke-width": "2",
+                            }
+                        }
+                    }
+                    p { class: "text-xs", "{count} check-ins, averages beside each C" }
+                }
+            }
+            div { class: "flex gap-2 flex-wrap",
+                for (quality, average, off) in legend {
+                    button {
+                        class: UNSELECTED,
+                        style: if off { "opacity: 0.4;" } else { "" },
+                        title: "Show or hide on the chart",
+                        onclick: move |_| {
+                            let mut hidden = hidden.write();
+                            match hidden.iter().position(|other| *other == quality) {
+                                Some(i) => {
+                                    hidden.remove(i);
+                                }
+                                None => hidden.push(quality),
+                            }
+                        },
+                        span { style: "color: {line_colour(quality)};", "● " }
+                        "{quality.name()} {average}"
+                    }
+                }
+            }
+        }
+    })
+}
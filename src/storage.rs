@@ -2,8 +2,45 @@ use dioxus::prelude::*;
 use dioxus_signals::Signal;
 use dioxus_std::storage::*;
 use serde::{Serialize, de::DeserializeOwned};
+use std::{cell::RefCell, collections::HashMap};
+
+thread_local! {
+    /// Set by [`use_memory_backend`], when present nothing touches the platform's storage
+    static MEMORY: RefCell<Option<HashMap<String, String>>> = RefCell::new(None);
+}
+
+/// Keeps everything in memory for the current thread instead of the platform's storage, for tests
+pub fn use_memory_backend() {
+    MEMORY.with(|memory| *memory.borrow_mut() = Some(HashMap::new()));
+}
+
+fn in_memory() -> bool {
+    MEMORY.with(|memory| memory.borrow().is_some())
+}
+
+fn memory_get<T: DeserializeOwned>(key: &str) -> Option<T> {
+    MEMORY.with(|memory| {
+        memory
+            .borrow()
+            .as_ref()?
+            .get(key)
+            .and_then(|value| serde_json::from_str(value).ok())
+    })
+}
+
+fn memory_set<T: Serialize>(key: String, value: &T) {
+    MEMORY.with(|memory| {
+        if let Some(memory) = memory.borrow_mut().as_mut() {
+            memory.insert(key, serde_json::to_string(value).unwrap());
+        }
+    });
+}
 
 pub fn store<T: Serialize + Send + Sync + Clone + 'static>(key: impl ToString, value: T) -> bool {
+    if in_memory() {
+        memory_set(key.to_string(), &value);
+        return true;
+    }
     LocalStorage::set(
         key.to_string(),
         &value,
@@ -12,11 +49,34 @@ pub fn store<T: Serialize + Send + Sync + Clone + 'static>(key: impl ToString, v
 }
 
 pub fn retrieve<T: Serialize + DeserializeOwned + Send + Sync + Clone + 'static>(key: impl ToString, init: impl FnOnce() -> T) -> T {
+    if in_memory() {
+        return memory_get(&key.to_string()).unwrap_or_else(|| {
+            let value = init();
+            memory_set(key.to_string(), &value);
+            value
+        });
+    }
     get_from_storage::<LocalStorage, T>(key.to_string(), init)
     // init()
 }
 
+/// Like [`retrieve`] but doesn't store anything when the key is missing
+pub fn try_retrieve<T: DeserializeOwned>(key: impl ToString) -> Option<T> {
+    if in_memory() {
+        return memory_get(&key.to_string());
+    }
+    LocalStorage::get(&key.to_string())
+}
+
 pub fn use_synced_storage<T: Serialize + DeserializeOwned + Clone + Send + Sync + PartialEq + 'static>(cx: &ScopeState, key: impl ToString, init: impl FnOnce() -> T) -> Signal<T> {
+    if in_memory() {
+        let key = key.to_string();
+        let signal = dioxus_signals::use_signal(cx, || retrieve(key.clone(), init));
+        dioxus_signals::use_effect(cx, move || {
+            store(key.clone(), signal.read().clone());
+        });
+        return signal;
+    }
     dioxus_std::storage::use_synced_storage::<LocalStorage, T>(cx, key.to_string(), init)
     // dioxus_signals::use_signal(cx, init)
 }
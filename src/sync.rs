@@ -0,0 +1,348 @@
+pub mod remote;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+
+use crate::data::{Branch, ChatData, CheckIns, EmotionTag, Message, Messages, Persona, Personas};
+use crate::storage;
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Orders changes between replicas, a lamport counter with the replica id as tie breaker
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Stamp {
+    pub counter: u64,
+    pub replica: Uuid,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Clock {
+    replica: Uuid,
+    counter: u64,
+}
+
+impl Clock {
+    fn new() -> Self {
+        Clock {
+            replica: Uuid::new_v4(),
+            counter: 0,
+        }
+    }
+
+    fn tick(&mut self) -> Stamp {
+        self.counter += 1;
+        Stamp {
+            counter: self.counter,
+            replica: self.replica,
+        }
+    }
+
+    fn witness(&mut self, counter: u64) {
+        self.counter = self.counter.max(counter);
+    }
+}
+
+/// Last writer wins register
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Lww<T> {
+    pub stamp: Stamp,
+    pub value: T,
+}
+
+impl<T: Clone + PartialEq> Lww<T> {
+    fn new(stamp: Stamp, value: T) -> Self {
+        Lww { stamp, value }
+    }
+
+    /// Records a local write, only taking a new stamp if the value actually changed
+    fn update(&mut self, clock: &mut Clock, value: &T) {
+        if self.value != *value {
+            *self = Lww::new(clock.tick(), value.clone());
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        if other.stamp > self.stamp {
+            *self = other.clone();
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PersonaReplica {
+    /// When the persona was first seen, personas are ordered by this
+    pub created: Stamp,
+    pub persona: Lww<Persona>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MessageReplica {
+    /// When the message was first seen, breaks ties between messages sent at the same time
+    pub created: Stamp,
+    /// `None` once the message has been deleted on any replica
+    pub message: Option<Message>,
+    /// The only part of a message that changes after it's sent
+    #[serde(default)]
+    pub emotions: Lww<Vec<EmotionTag>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChatReplica {
+    pub created: Stamp,
+    pub name: Lww<String>,
+    pub deleted: Lww<bool>,
+    /// Personas are never removed from a chat, so this only grows
+    pub added_personas: IndexMap<Uuid, Stamp>,
+    pub messages: IndexMap<Uuid, MessageReplica>,
+    /// Set when the chat is made and never changed
+    #[serde(default)]
+    pub branch: Option<Branch>,
+    /// When the chat was started in unix milliseconds, 0 if not known
+    #[serde(default)]
+    pub started: i64,
+    #[serde(default)]
+    pub check_ins: Lww<CheckIns>,
+}
+
+impl ChatReplica {
+    fn new(clock: &mut Clock, chat: &ChatData) -> Self {
+        let created = clock.tick();
+        let mut replica = ChatReplica {
+            created,
+            name: Lww::new(created, chat.name.clone()),
+            deleted: Lww::new(created, false),
+            added_personas: IndexMap::new(),
+            messages: IndexMap::new(),
+            branch: chat.branch,
+            started: chat.started,
+            check_ins: Lww::new(created, chat.check_ins),
+        };
+        replica.observe(clock, chat);
+        replica
+    }
+
+    fn observe(&mut self, clock: &mut Clock, chat: &ChatData) {
+        self.name.update(clock, &chat.name);
+        self.check_ins.update(clock, &chat.check_ins);
+        self.deleted.update(clock, &false);
+        for persona in chat.added_personas.iter() {
+            if !self.added_personas.contains_key(persona) {
+                self.added_personas.insert(*persona, clock.tick());
+            }
+        }
+        for msg in chat.messages.msgs.iter() {
+            match self.messages.get_mut(&msg.uuid) {
+                Some(replica) => replica.emotions.update(clock, &msg.emotions),
+                None => {
+                    let created = clock.tick();
+                    self.messages.insert(
+                        msg.uuid,
+                        MessageReplica {
+                            created,
+                            message: Some(msg.clone()),
+                            emotions: Lww::new(created, msg.emotions.clone()),
+                        },
+                    );
+                }
+            }
+        }
+        // Anything we knew about that is no longer in the chat was deleted here
+        let kept: IndexSet<Uuid> = chat.messages.msgs.iter().map(|msg| msg.uuid).collect();
+        for (uuid, replica) in self.messages.iter_mut() {
+            if !kept.contains(uuid) {
+                replica.message = None;
+            }
+        }
+    }
+
+    fn merge(&mut self, other: &ChatReplica) {
+        self.created = self.created.min(other.created);
+        self.branch = self.branch.or(other.branch);
+        if self.started == 0 || (other.started > 0 && other.started < self.started) {
+            self.started = other.started;
+        }
+        self.name.merge(&other.name);
+        self.check_ins.merge(&other.check_ins);
+        self.deleted.merge(&other.deleted);
+        for (persona, stamp) in other.added_personas.iter() {
+            let entry = self.added_personas.entry(*persona).or_insert(*stamp);
+            *entry = (*entry).min(*stamp);
+        }
+        for (uuid, theirs) in other.messages.iter() {
+            match self.messages.get_mut(uuid) {
+                Some(ours) => {
+                    ours.created = ours.created.min(theirs.created);
+                    ours.emotions.merge(&theirs.emotions);
+                    if theirs.message.is_none() {
+                        ours.message = None;
+                    }
+                }
+                None => {
+                    self.messages.insert(*uuid, theirs.clone());
+                }
+            }
+        }
+    }
+
+    fn to_chat(&self, uuid: Uuid) -> ChatData {
+        let mut added_personas: Vec<(&Uuid, &Stamp)> = self.added_personas.iter().collect();
+        added_personas.sort_by_key(|(uuid, stamp)| (**stamp, **uuid));
+        let added_personas: IndexSet<Uuid> = added_personas.into_iter().map(|(uuid, _)| *uuid).collect();
+
+        // By when they were sent like a merge would, old messages without a time keep the order they were seen in
+        let mut msgs: Vec<(Stamp, Message)> = self
+            .messages
+            .values()
+            .filter_map(|replica| {
+                let mut msg = replica.message.clone()?;
+                msg.emotions = replica.emotions.value.clone();
+                Some((replica.created, msg))
+            })
+            .collect();
+        msgs.sort_by_key(|(created, msg)| (msg.sent, *created, msg.uuid));
+        let msgs = msgs.into_iter().map(|(_, msg)| msg).collect();
+
+        ChatData {
+            uuid,
+            name: self.name.value.clone(),
+            messages: Messages { msgs },
+            active_persona: added_personas.first().copied().unwrap_or_default(),
+            added_personas,
+            current_message: String::new(),
+            branch: self.branch,
+            started: self.started,
+            // Sessions are followed on one device at a time
+            session: None,
+            check_ins: self.check_ins.value,
+        }
+    }
+}
+
+/// Mergeable copy of the personas and chats.
+///
+/// Local changes are folded in with [`SyncDoc::observe`], another replica's
+/// document is folded in with [`SyncDoc::merge`]. Merging is commutative and
+/// idempotent, so replicas that have seen the same changes read back the same
+/// personas and chats no matter the order they synced in.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncDoc {
+    clock: Clock,
+    pub personas: IndexMap<Uuid, PersonaReplica>,
+    pub chats: IndexMap<Uuid, ChatReplica>,
+}
+
+impl SyncDoc {
+    pub fn new() -> Self {
+        SyncDoc {
+            clock: Clock::new(),
+            ..Default::default()
+        }
+    }
+
+    /// The sync document kept for this device
+    pub fn load() -> Self {
+        storage::retrieve("ifs_sync", SyncDoc::new)
+    }
+
+    pub fn save(&self) {
+        storage::store("ifs_sync", self.clone());
+    }
+
+    pub fn replica(&self) -> Uuid {
+        self.clock.replica
+    }
+
+    /// Records the differences between the document and the current local state as new changes
+    pub fn observe(&mut self, personas: &Personas, chats: &[ChatData]) {
+        let clock = &mut self.clock;
+        for (uuid, persona) in personas.iter() {
+            match self.personas.get_mut(uuid) {
+                Some(replica) => replica.persona.update(clock, persona),
+                None => {
+                    let created = clock.tick();
+                    self.personas.insert(
+                        *uuid,
+                        PersonaReplica {
+                            created,
+                            persona: Lww::new(created, persona.clone()),
+                        },
+                    );
+                }
+            }
+        }
+
+        for chat in chats {
+            match self.chats.get_mut(&chat.uuid) {
+                Some(replica) => replica.observe(clock, chat),
+                None => {
+                    self.chats.insert(chat.uuid, ChatReplica::new(clock, chat));
+                }
+            }
+        }
+        let kept: IndexSet<Uuid> = chats.iter().map(|chat| chat.uuid).collect();
+        for (uuid, replica) in self.chats.iter_mut() {
+            if !kept.contains(uuid) {
+                replica.deleted.update(clock, &true);
+            }
+        }
+    }
+
+    /// Folds in the changes from another replica
+    pub fn merge(&mut self, other: &SyncDoc) {
+        self.clock.witness(other.clock.counter);
+        for (uuid, theirs) in other.personas.iter() {
+            match self.personas.get_mut(uuid) {
+                Some(ours) => {
+                    ours.created = ours.created.min(theirs.created);
+                    ours.persona.merge(&theirs.persona);
+                }
+                None => {
+                    self.personas.insert(*uuid, theirs.clone());
+                }
+            }
+        }
+        for (uuid, theirs) in other.chats.iter() {
+            match self.chats.get_mut(uuid) {
+                Some(ours) => ours.merge(theirs),
+                None => {
+                    self.chats.insert(*uuid, theirs.clone());
+                }
+            }
+        }
+    }
+
+    pub fn personas(&self) -> Personas {
+        let mut personas: Vec<(&Uuid, &PersonaReplica)> = self.personas.iter().collect();
+        personas.sort_by_key(|(uuid, replica)| (replica.created, **uuid));
+        Personas(
+            personas
+                .into_iter()
+                .map(|(uuid, replica)| (*uuid, replica.persona.value.clone()))
+                .collect(),
+        )
+    }
+
+    /// Every chat that hasn't been deleted, oldest first
+    pub fn chats(&self) -> Vec<ChatData> {
+        let mut chats: Vec<(&Uuid, &ChatReplica)> = self
+            .chats
+            .iter()
+            .filter(|(_, replica)| !replica.deleted.value)
+            .collect();
+        chats.sort_by_key(|(uuid, replica)| (replica.created, **uuid));
+        chats
+            .into_iter()
+            .map(|(uuid, replica)| replica.to_chat(*uuid))
+            .collect()
+    }
+
+    /// Serializes the document for a sync file
+    pub fn to_file_string(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Reads another replica's document from a sync file
+    pub fn from_file_str(s: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(s)
+    }
+}
@@ -0,0 +1,163 @@
+use super::SyncDoc;
+use crate::data::AppState;
+use crate::storage;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    XChaCha20Poly1305, XNonce,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// A blob as stored by the sync server
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Blob {
+    pub revision: u64,
+    /// Base64 of the encrypted sync document
+    pub data: String,
+}
+
+/// Response to `GET /v1/{user}/blobs?since={revision}`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Blobs {
+    /// The latest revision the server has for the user
+    pub revision: u64,
+    pub blobs: Vec<Blob>,
+}
+
+/// Response to `POST /v1/{user}/blobs`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Pushed {
+    pub revision: u64,
+}
+
+/// Where and as who to sync, persisted on the device
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RemoteConfig {
+    /// e.g. `http://192.168.1.10:8754`
+    pub server: String,
+    pub user: String,
+    /// Sent to the server, which only checks it matches the one `user` was claimed with
+    pub token: String,
+    /// Never leaves the device, the server only ever sees encrypted documents
+    pub passphrase: String,
+    /// Latest server revision already merged
+    pub revision: u64,
+}
+
+impl RemoteConfig {
+    pub fn load() -> Self {
+        storage::retrieve("ifs_sync_remote", RemoteConfig::default)
+    }
+
+    pub fn save(&self) {
+        storage::store("ifs_sync_remote", self.clone());
+    }
+
+    fn blobs_url(&self) -> String {
+        format!("{}/v1/{}/blobs", self.server.trim_end_matches('/'), self.user)
+    }
+
+    /// Derives the key from the passphrase, which is deliberately slow so do it once per sync
+    pub fn cipher(&self) -> JournalCipher {
+        let mut key = [0u8; 32];
+        argon2::Argon2::default()
+            .hash_password_into(self.passphrase.as_bytes(), format!("let-me-talk:{}", self.user).as_bytes(), &mut key)
+            .expect("key length is valid");
+        JournalCipher(XChaCha20Poly1305::new(&key.into()))
+    }
+}
+
+pub struct JournalCipher(XChaCha20Poly1305);
+
+impl JournalCipher {
+    /// Nonce followed by the ciphertext of the document
+    pub fn encrypt(&self, doc: &SyncDoc) -> Vec<u8> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .0
+            .encrypt(&nonce, serde_json::to_vec(doc).unwrap().as_slice())
+            .expect("encrypting to a vec can't fail");
+        [nonce.as_slice(), &ciphertext].concat()
+    }
+
+    pub fn decrypt(&self, blob: &[u8]) -> Result<SyncDoc, RemoteError> {
+        if blob.len() < 24 {
+            return Err(RemoteError::Decrypt);
+        }
+        let (nonce, ciphertext) = blob.split_at(24);
+        let plaintext = self
+            .0
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| RemoteError::Decrypt)?;
+        serde_json::from_slice(&plaintext).map_err(|_| RemoteError::Decrypt)
+    }
+}
+
+#[derive(Debug)]
+pub enum RemoteError {
+    NotConfigured,
+    Http(reqwest::Error),
+    /// Wrong passphrase, or the blob wasn't written by this app
+    Decrypt,
+}
+
+impl Display for RemoteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RemoteError::NotConfigured => write!(f, "set a server, user, token and passphrase first"),
+            RemoteError::Http(e) => write!(f, "couldn't reach the sync server: {e}"),
+            RemoteError::Decrypt => write!(f, "couldn't decrypt the journal, check the passphrase"),
+        }
+    }
+}
+
+impl From<reqwest::Error> for RemoteError {
+    fn from(e: reqwest::Error) -> Self {
+        RemoteError::Http(e)
+    }
+}
+
+/// Pulls everything pushed since the last sync, merges it into the app and
+/// pushes the merged result back
+pub async fn sync_remote(app_state: AppState, config: &mut RemoteConfig) -> Result<(), RemoteError> {
+    if config.server.is_empty() || config.user.is_empty() || config.token.is_empty() || config.passphrase.is_empty() {
+        return Err(RemoteError::NotConfigured);
+    }
+    let client = reqwest::Client::new();
+    let cipher = config.cipher();
+
+    let pulled: Blobs = client
+        .get(config.blobs_url())
+        .query(&[("since", config.revision)])
+        .bearer_auth(&config.token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let mut remote = SyncDoc::default();
+    for blob in pulled.blobs.iter() {
+        let bytes = BASE64.decode(&blob.data).map_err(|_| RemoteError::Decrypt)?;
+        remote.merge(&cipher.decrypt(&bytes)?);
+    }
+
+    let doc = app_state.sync(Some(&remote));
+
+    // Nothing on the server yet means the user may still need claiming, which is
+    // harmless if it's already ours
+    let claim = if pulled.revision == 0 { "true" } else { "false" };
+    client
+        .post(config.blobs_url())
+        .query(&[("claim", claim)])
+        .bearer_auth(&config.token)
+        .body(cipher.encrypt(&doc))
+        .send()
+        .await?
+        .error_for_status()?;
+    // Our own push gets pulled again next time, merging is idempotent so that's harmless
+    // and it means we can't skip over anything pushed by another device in between
+    config.revision = pulled.revision;
+    config.save();
+    Ok(())
+}
@@ -0,0 +1,216 @@
+use super::remote::{Blob, Blobs, Pushed};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Each blob is a whole merged journal, so older ones only matter to devices that haven't
+/// synced for a while, and even those catch up from the newest
+pub const KEPT_BLOBS: usize = 16;
+/// Largest journal accepted in one push
+pub const MAX_BLOB_BYTES: usize = 32 * 1024 * 1024;
+
+/// What a token gets for a user
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    Granted,
+    /// The user was claimed with another token
+    Denied,
+    /// Nobody has pushed as the user yet
+    Unclaimed,
+}
+
+/// Keeps each user's latest encrypted blobs as numbered files under `root/{user}/`,
+/// along with a hash of the token that claimed the user
+pub struct FileStore {
+    root: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(root: impl Into<PathBuf>) -> io::Result<Self> {
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+        Ok(FileStore { root })
+    }
+
+    fn user_dir(&self, user: &str) -> PathBuf {
+        self.root.join(user)
+    }
+
+    /// Checks the token against the one stored for the user, claiming the user with it
+    /// if `claim` is set and nobody has yet
+    pub fn authorize(&self, user: &str, token: &str, claim: bool) -> io::Result<Access> {
+        let hash = format!("{:x}", Sha256::digest(token.as_bytes()));
+        let path = self.user_dir(user).join("token");
+        match fs::read_to_string(&path) {
+            Ok(stored) if stored.trim() == hash => Ok(Access::Granted),
+            Ok(_) => Ok(Access::Denied),
+            Err(e) if e.kind() == io::ErrorKind::NotFound && claim => {
+                fs::create_dir_all(self.user_dir(user))?;
+                // Only one of two pushes racing to claim the user gets to write the token
+                match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+                    Ok(mut file) => {
+                        file.write_all(hash.as_bytes())?;
+                        Ok(Access::Granted)
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::AlreadyExists => self.authorize(user, token, false),
+                    Err(e) => Err(e),
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Access::Unclaimed),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn revisions(&self, user: &str) -> io::Result<Vec<u64>> {
+        let mut revisions = Vec::new();
+        for entry in fs::read_dir(self.user_dir(user))? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "blob") {
+                if let Some(revision) = path.file_stem().and_then(|stem| stem.to_str()?.parse().ok()) {
+                    revisions.push(revision);
+                }
+            }
+        }
+        revisions.sort();
+        Ok(revisions)
+    }
+
+    fn blob_path(&self, user: &str, revision: u64) -> PathBuf {
+        self.user_dir(user).join(format!("{revision:020}.blob"))
+    }
+
+    /// Stores the blob as the next revision, dropping all but the latest [`KEPT_BLOBS`]
+    pub fn push(&self, user: &str, blob: &[u8]) -> io::Result<u64> {
+        let revision = self.revisions(user)?.last().copied().unwrap_or(0) + 1;
+        fs::write(self.blob_path(user, revision), blob)?;
+        for old in self.revisions(user)?.iter().rev().skip(KEPT_BLOBS) {
+            fs::remove_file(self.blob_path(user, *old))?;
+        }
+        Ok(revision)
+    }
+
+    pub fn since(&self, user: &str, since: u64) -> io::Result<Blobs> {
+        let revisions = self.revisions(user)?;
+        let mut blobs = Vec::new();
+        for revision in revisions.iter().filter(|revision| **revision > since) {
+            blobs.push(Blob {
+                revision: *revision,
+                data: BASE64.encode(fs::read(self.blob_path(user, *revision))?),
+            });
+        }
+        Ok(Blobs {
+            revision: revisions.last().copied().unwrap_or(0),
+            blobs,
+        })
+    }
+}
+
+/// User names end up as directory names, so keep them boring
+fn valid_user(user: &str) -> bool {
+    !user.is_empty()
+        && user.len() <= 64
+        && user
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes()).unwrap()
+}
+
+fn respond(request: Request, status: u16, body: String) {
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"))
+        // The web app is served from a different origin than the sync server
+        .with_header(header("Access-Control-Allow-Origin", "*"))
+        .with_header(header("Access-Control-Allow-Headers", "Authorization, Content-Type"))
+        .with_header(header("Access-Control-Allow-Methods", "GET, POST, OPTIONS"));
+    if let Err(e) = request.respond(response) {
+        log::warn!("failed to respond: {e}");
+    }
+}
+
+fn error(request: Request, status: u16, message: &str) {
+    respond(request, status, serde_json::json!({ "error": message }).to_string())
+}
+
+fn param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+}
+
+fn handle(store: &FileStore, mut request: Request) {
+    if request.method() == &Method::Options {
+        return respond(request, 204, String::new());
+    }
+
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let user = match path.trim_matches('/').split('/').collect::<Vec<_>>()[..] {
+        ["v1", user, "blobs"] if valid_user(user) => user.to_string(),
+        _ => return error(request, 404, "not found"),
+    };
+
+    let token = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .and_then(|header| header.value.as_str().strip_prefix("Bearer "))
+        .map(str::to_string);
+    let Some(token) = token else {
+        return error(request, 401, "missing token");
+    };
+    // Only a push that asks to can claim a user, so reading never creates one
+    let claim = request.method() == &Method::Post && param(query, "claim") == Some("true");
+    let access = match store.authorize(&user, &token, claim) {
+        Ok(access) => access,
+        Err(e) => return error(request, 500, &e.to_string()),
+    };
+
+    match (request.method(), access) {
+        (_, Access::Denied) => error(request, 403, "wrong token for this user"),
+        (Method::Get, Access::Unclaimed) => respond(request, 200, serde_json::to_string(&Blobs::default()).unwrap()),
+        (Method::Post, Access::Unclaimed) => error(request, 403, "no such user, push with ?claim=true to create it"),
+        (Method::Get, Access::Granted) => {
+            let since = param(query, "since").and_then(|since| since.parse().ok()).unwrap_or(0);
+            match store.since(&user, since) {
+                Ok(blobs) => respond(request, 200, serde_json::to_string(&blobs).unwrap()),
+                Err(e) => error(request, 500, &e.to_string()),
+            }
+        }
+        (Method::Post, Access::Granted) => {
+            if request.body_length().is_some_and(|length| length > MAX_BLOB_BYTES) {
+                return error(request, 413, "journal too large");
+            }
+            let mut blob = Vec::new();
+            if let Err(e) = request.as_reader().take(MAX_BLOB_BYTES as u64 + 1).read_to_end(&mut blob) {
+                return error(request, 400, &e.to_string());
+            }
+            if blob.len() > MAX_BLOB_BYTES {
+                return error(request, 413, "journal too large");
+            }
+            match store.push(&user, &blob) {
+                Ok(revision) => respond(request, 200, serde_json::to_string(&Pushed { revision }).unwrap()),
+                Err(e) => error(request, 500, &e.to_string()),
+            }
+        }
+        _ => error(request, 405, "method not allowed"),
+    }
+}
+
+/// Serves the sync API until the process is stopped
+pub fn serve(addr: &str, store: FileStore) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let server = Server::http(addr)?;
+    log::info!("serving sync on http://{addr}");
+    for request in server.incoming_requests() {
+        handle(&store, request);
+    }
+    Ok(())
+}
@@ -16,7 +16,18 @@ module.exports = {
     },
   ],
   theme: {
-      extend: {},
+      extend: {
+          // Set by the theme's stylesheet, see src/colours/theme.rs
+          colors: {
+              surface: "var(--lmt-surface)",
+              panel: "var(--lmt-panel)",
+              sidebar: "var(--lmt-sidebar)",
+              raised: "var(--lmt-raised)",
+              ink: "var(--lmt-ink)",
+              accent: "var(--lmt-accent)",
+              "on-accent": "var(--lmt-on-accent)",
+          },
+      },
   },
   plugins: [],
 }
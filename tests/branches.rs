@@ -0,0 +1,67 @@
+mod harness;
+
+use dioxus::html::input_data::keyboard_types::{Key, Modifiers};
+use harness::Harness;
+use let_me_talk::data::{Branch, ChatData, Personas};
+use let_me_talk::sync::SyncDoc;
+use uuid::Uuid;
+
+fn chat_with(messages: &[&str]) -> ChatData {
+    let mut chat = ChatData::new(Uuid::new_v4());
+    for msg in messages {
+        chat.send(chat.active_persona, msg.to_string());
+    }
+    chat
+}
+
+#[test]
+fn a_branch_keeps_the_messages_up_to_its_point() {
+    let chat = chat_with(&["one", "two", "three"]);
+    let point = chat.messages.msgs[1].uuid;
+    let branch = chat.branch_from(&point).unwrap();
+
+    let texts: Vec<&str> = branch.messages.msgs.iter().map(|msg| msg.msg.as_str()).collect();
+    assert_eq!(texts, ["one", "two"]);
+    assert_ne!(branch.uuid, chat.uuid);
+    assert_eq!(branch.added_personas, chat.added_personas);
+    assert_eq!(branch.branch, Some(Branch { chat: chat.uuid, message: point }));
+    assert!(chat.branch_from(&Uuid::new_v4()).is_none());
+}
+
+#[test]
+fn branches_survive_syncing() {
+    let chat = chat_with(&["one"]);
+    let branch = chat.branch_from(&chat.messages.msgs[0].uuid).unwrap();
+    let mut doc = SyncDoc::new();
+    doc.observe(&Personas::starter(), &[chat.clone(), branch.clone()]);
+    let mut other = SyncDoc::new();
+    other.merge(&doc);
+
+    let synced = other.chats().into_iter().find(|synced| synced.uuid == branch.uuid).unwrap();
+    assert_eq!(synced.branch, branch.branch);
+}
+
+#[test]
+fn branching_from_a_message_opens_the_branch_under_its_chat() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    let original = harness.active_chat();
+    harness.type_text("What if I'd said no");
+    harness.press(Key::Enter, Modifiers::empty());
+
+    harness.click_text("Branch from here");
+    let branch = harness.active_chat();
+    assert!(branch != original);
+    assert_eq!(branch.branch.unwrap().chat, *original.uuid());
+    assert_eq!(branch.messages.read().msgs, original.messages.read().msgs);
+
+    // Writing in the branch leaves the original alone
+    harness.type_text("No.");
+    harness.press(Key::Enter, Modifiers::empty());
+    assert_eq!(original.messages.read().msgs.len(), 1);
+    assert_eq!(branch.messages.read().msgs.len(), 2);
+
+    let tree = harness.state().chats.read().tree();
+    let depths: Vec<(Uuid, usize)> = tree.iter().map(|(chat, depth)| (*chat.uuid(), *depth)).collect();
+    assert_eq!(depths, [(*original.uuid(), 0), (*branch.uuid(), 1)]);
+}
@@ -0,0 +1,107 @@
+mod harness;
+
+use dioxus::html::input_data::keyboard_types::{Key, Modifiers};
+use harness::Harness;
+use let_me_talk::colours::Rgb;
+use let_me_talk::data::Persona;
+
+/// Adds a persona to the open chat and makes it the active one
+fn add_persona(harness: &Harness, name: &str) -> uuid::Uuid {
+    let uuid = harness.state().personas.write().push(Persona {
+        name: name.to_string(),
+        colour: Rgb(0xaa, 0x22, 0x33),
+    });
+    harness.active_chat().add_persona(uuid);
+    uuid
+}
+
+/// A fresh install has no chats, so every flow starts from the sidebar
+fn with_chat() -> Harness {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    harness
+}
+
+#[test]
+fn starts_with_me_and_no_chats() {
+    let harness = Harness::new();
+    let state = harness.state();
+
+    assert_eq!(state.personas.read().count(), 1);
+    assert_eq!(state.personas.read().get_index(0).unwrap().1.name, "Me");
+    assert_eq!(state.chats.read().chats().count(), 0);
+    assert!(state.active_chat.read().is_none());
+    assert!(harness.html().contains("Let Me Talk"));
+}
+
+#[test]
+fn enter_sends_the_typed_message() {
+    let mut harness = with_chat();
+    let me = *harness.state().personas.read().get_index(0).unwrap().0;
+
+    harness.type_text("hello there");
+    harness.press(Key::Enter, Modifiers::empty());
+
+    let chat = harness.active_chat();
+    let messages = chat.messages.read();
+    assert_eq!(messages.msgs.len(), 1);
+    assert_eq!(messages.msgs[0].msg, "hello there");
+    assert_eq!(messages.msgs[0].persona, me);
+    assert!(chat.current_message.read().is_empty());
+    assert!(harness.html().contains("hello there"));
+}
+
+#[test]
+fn enter_on_an_empty_message_does_nothing() {
+    let mut harness = with_chat();
+
+    harness.press(Key::Enter, Modifiers::empty());
+
+    assert!(harness.active_chat().messages.read().msgs.is_empty());
+}
+
+#[test]
+fn tab_cycles_personas_and_messages_are_sent_as_the_active_one() {
+    let mut harness = with_chat();
+    let me = *harness.state().personas.read().get_index(0).unwrap().0;
+    let critic = add_persona(&harness, "Critic");
+    let planner = add_persona(&harness, "Planner");
+    let chat = harness.active_chat();
+    assert_eq!(*chat.active_persona.read(), planner);
+
+    harness.press(Key::Tab, Modifiers::empty());
+    assert_eq!(*chat.active_persona.read(), me);
+    harness.press(Key::Tab, Modifiers::empty());
+    assert_eq!(*chat.active_persona.read(), critic);
+    harness.press(Key::Tab, Modifiers::SHIFT);
+    assert_eq!(*chat.active_persona.read(), me);
+    harness.press(Key::Tab, Modifiers::SHIFT);
+    assert_eq!(*chat.active_persona.read(), planner);
+
+    harness.type_text("one step at a time");
+    harness.press(Key::Enter, Modifiers::empty());
+
+    assert_eq!(chat.messages.read().msgs[0].persona, planner);
+    let html = harness.html();
+    assert!(html.contains("Planner"));
+    assert!(html.contains("one step at a time"));
+}
+
+#[test]
+fn sidebar_new_chat_and_delete() {
+    let mut harness = with_chat();
+    let first = *harness.active_chat().uuid();
+
+    harness.click_text("New Chat");
+
+    let state = harness.state();
+    assert_eq!(state.chats.read().chats().count(), 2);
+    let second = *harness.active_chat().uuid();
+    assert_ne!(first, second);
+
+    harness.click_text("x");
+
+    assert_eq!(state.chats.read().chats().count(), 1);
+    assert_eq!(*state.chats.read().get_index(0).unwrap().uuid(), first);
+    assert!(state.active_chat.read().is_none());
+}
@@ -0,0 +1,80 @@
+mod harness;
+
+use harness::Harness;
+use let_me_talk::data::{averages, trend, ChatData, CheckIn, CheckIns, Moment, Personas, Quality};
+use let_me_talk::sync::SyncDoc;
+use uuid::Uuid;
+
+fn check_in(taken: i64, rating: u8) -> CheckIn {
+    CheckIn {
+        taken,
+        ratings: [rating; 8],
+    }
+}
+
+#[test]
+fn ratings_stay_between_one_and_ten() {
+    let mut start = CheckIn::default();
+    start.rate(Quality::Calm, 0);
+    start.rate(Quality::Courage, 12);
+    assert_eq!(start.rating(Quality::Calm), 1);
+    assert_eq!(start.rating(Quality::Courage), 10);
+
+    let mut end = start;
+    end.rate(Quality::Calm, 7);
+    let check_ins = CheckIns {
+        start: Some(start),
+        end: Some(end),
+    };
+    assert_eq!(check_ins.change(Quality::Calm), Some(6));
+    assert_eq!(CheckIns::default().change(Quality::Calm), None);
+    assert!(start.summary().starts_with("Calm 1, Curiosity 5"));
+}
+
+#[test]
+fn trends_go_oldest_first_within_the_span() {
+    let sessions = [
+        CheckIns { start: Some(check_in(300, 4)), end: Some(check_in(310, 8)) },
+        CheckIns { start: Some(check_in(100, 2)), end: None },
+        CheckIns { start: Some(check_in(50, 9)), end: Some(check_in(60, 9)) },
+    ];
+    let points = trend(&sessions, Moment::Start, 100);
+    let taken: Vec<i64> = points.iter().map(|point| point.taken).collect();
+    assert_eq!(taken, [100, 300]);
+    assert_eq!(averages(&points).unwrap()[0], 3.0);
+    assert_eq!(trend(&sessions, Moment::End, 0).len(), 2);
+    assert!(averages(&[]).is_none());
+}
+
+#[test]
+fn check_ins_are_saved_synced_and_exported_with_the_chat() {
+    let mut chat = ChatData::new(Uuid::new_v4());
+    chat.check_ins.start = Some(check_in(100, 6));
+    let json = serde_json::to_string(&chat).unwrap();
+    assert_eq!(serde_json::from_str::<ChatData>(&json).unwrap().check_ins, chat.check_ins);
+    assert!(chat.to_markdown(&Personas::starter()).contains("Start check-in: Calm 6, Curiosity 6"));
+
+    let mut doc = SyncDoc::new();
+    doc.observe(&Personas::starter(), &[chat.clone()]);
+    let mut other = SyncDoc::new();
+    other.merge(&doc);
+    assert_eq!(other.chats()[0].check_ins, chat.check_ins);
+
+    // Chats saved before check-ins have none
+    let older = json.replace(r#","check_ins":{"start":{"taken":100,"ratings":[6,6,6,6,6,6,6,6]},"end":null}"#, "");
+    assert_ne!(older, json);
+    assert_eq!(serde_json::from_str::<ChatData>(&older).unwrap().check_ins, CheckIns::default());
+}
+
+#[test]
+fn a_check_in_is_taken_from_the_chat() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    let chat = harness.active_chat();
+    harness.click_text("Start check-in");
+    harness.click_text("Save");
+    let start = chat.check_ins.read().start.unwrap();
+    assert_eq!(start.ratings, [5; 8]);
+    assert!(start.taken > 0);
+    assert!(harness.html().contains("Start check-in 5.0"));
+}
@@ -0,0 +1,88 @@
+use let_me_talk::cli::{command, is_command};
+use let_me_talk::colours::Rgb;
+use let_me_talk::data::{Journal, JournalExport, MessageKind, Persona, Personas};
+use let_me_talk::storage;
+
+fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(str::to_string).collect()
+}
+
+fn journal() -> Journal {
+    storage::use_memory_backend();
+    let mut journal = Journal::load();
+    for name in ["Monday", "Tuesday"] {
+        let uuid = journal.new_chat();
+        journal.chat_mut(&uuid).unwrap().name = name.to_string();
+    }
+    journal
+}
+
+#[test]
+fn chats_are_found_by_name_or_the_start_of_their_id() {
+    let journal = journal();
+    let tuesday = journal.chats[1].uuid;
+    assert_eq!(journal.find_chat("Tuesday").unwrap().uuid, tuesday);
+    assert_eq!(journal.find_chat(&tuesday.to_string()[..8]).unwrap().uuid, tuesday);
+    assert!(journal.find_chat("Sunday").is_none());
+    assert!(journal.find_chat("").is_none());
+}
+
+#[test]
+fn missing_arguments_give_the_usage() {
+    let mut journal = journal();
+    assert!(command(&mut journal, args("show")).unwrap_err().starts_with("usage: let-me-talk show"));
+    assert!(command(&mut journal, Vec::new()).unwrap_err().starts_with("usage"));
+    assert!(command(&mut journal, args("frobnicate")).unwrap_err().starts_with("usage"));
+    assert_eq!(command(&mut journal, args("say Me hello --chat")).unwrap_err(), "--chat needs a value");
+    assert!(command(&mut journal, args("say --kind shout Me hello")).unwrap_err().starts_with("unknown kind shout"));
+    assert!(command(&mut journal, args("show Sunday")).unwrap_err().starts_with("no chat called Sunday"));
+}
+
+#[test]
+fn say_takes_its_flags_before_the_persona() {
+    let mut journal = journal();
+    command(&mut journal, args("say --kind narration --chat Monday Me The room goes quiet")).unwrap();
+    let monday = journal.find_chat("Monday").unwrap();
+    let msg = &monday.messages.msgs[0];
+    assert_eq!(msg.msg, "The room goes quiet");
+    assert_eq!(msg.kind, MessageKind::Narration);
+    assert!(journal.find_chat("Tuesday").unwrap().messages.msgs.is_empty());
+}
+
+#[test]
+fn flags_end_at_the_message_or_a_double_dash() {
+    let mut journal = journal();
+    command(&mut journal, args("say --chat Monday Me use --kind carefully")).unwrap();
+    command(&mut journal, args("say --chat Monday -- Me --chat is a flag")).unwrap();
+    let monday = journal.find_chat("Monday").unwrap();
+    let texts: Vec<&str> = monday.messages.msgs.iter().map(|msg| msg.msg.as_str()).collect();
+    assert_eq!(texts, ["use --kind carefully", "--chat is a flag"]);
+    assert!(monday.messages.msgs.iter().all(|msg| msg.kind == MessageKind::Speech));
+
+    assert!(command(&mut journal, args("say --knd aside Me hi")).unwrap_err().starts_with("unknown option --knd"));
+}
+
+#[test]
+fn only_known_commands_leave_the_app_to_the_terminal() {
+    assert!(is_command("list") && is_command("new-chat") && is_command("--help"));
+    assert!(!is_command("-psn_0_12345") && !is_command("--devtools") && !is_command("notes.json"));
+}
+
+#[test]
+fn imported_personas_without_a_colour_or_with_a_clashing_one_get_a_distinct_colour() {
+    let mut journal = journal();
+    let Rgb(r, g, b) = journal.personas.colours()[0];
+    let mut personas = Personas::new(Persona { name: "Teal".to_string(), colour: Rgb(0x00, 0x80, 0x80) });
+    let teal = *personas.get_index(0).unwrap().0;
+    let blank = personas.push(Persona { name: "Blank".to_string(), colour: Rgb::default() });
+    let lookalike = personas.push(Persona { name: "Lookalike".to_string(), colour: Rgb(r + 1, g, b) });
+
+    journal.import(JournalExport { personas, chats: Vec::new() });
+
+    let colour = |uuid| journal.personas.get(&uuid).unwrap().colour;
+    assert_eq!(colour(teal), Rgb(0x00, 0x80, 0x80));
+    assert_ne!(colour(blank), Rgb::default());
+    assert_ne!(colour(lookalike), Rgb(r + 1, g, b));
+    let me = Personas::new(Persona { name: "Me".to_string(), colour: Rgb(r, g, b) });
+    assert!(!me.colour_clashes(colour(lookalike)));
+}
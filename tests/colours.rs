@@ -0,0 +1,132 @@
+use let_me_talk::colours::{theme::*, vision::*, *};
+
+#[test]
+fn contrast_ratio_matches_wcag_extremes() {
+    assert!((Rgb::BLACK.contrast_ratio(Rgb::WHITE) - 21.0).abs() < 1e-9);
+    assert!((Rgb(0x77, 0x77, 0x77).contrast_ratio(Rgb(0x77, 0x77, 0x77)) - 1.0).abs() < 1e-9);
+    // #767676 is the lightest grey that passes AA on white
+    assert!(Rgb(0x76, 0x76, 0x76).contrast_ratio(Rgb::WHITE) >= AA_CONTRAST);
+    assert!(Rgb(0x77, 0x77, 0x77).contrast_ratio(Rgb::WHITE) < AA_CONTRAST);
+}
+
+#[test]
+fn saturated_colours_get_readable_text() {
+    // The old r+g+b midpoint picked white on pure green and black on violet
+    assert_eq!(best_text_colour(Rgb(0, 255, 0)).0, Rgb::BLACK);
+    assert_eq!(best_text_colour(Rgb(128, 0, 255)).0, Rgb::WHITE);
+    assert_eq!(best_text_colour(Rgb(0, 0, 255)).0, Rgb::WHITE);
+    // Mid greys are as far from black as from white, yet one of them still just reaches AA
+    let (_, grey) = best_text_colour(Rgb(0x77, 0x77, 0x77));
+    assert!(grey >= AA_CONTRAST && grey < 5.0);
+}
+
+#[test]
+fn bubble_contrast_is_measured_on_the_colour_the_theme_draws() {
+    let navy = Rgb(0x10, 0x10, 0x40);
+    let lifted = best_text_colour(bubble_colour(navy, true)).1;
+    let dark = Theme { mode: ThemeMode::Dark, ..Theme::default() };
+    let light = Theme { mode: ThemeMode::Light, ..Theme::default() };
+    assert_eq!(light.bubble_contrast(navy), best_text_colour(navy).1);
+    assert_eq!(dark.bubble_contrast(navy), lifted);
+    assert!(lifted < best_text_colour(navy).1);
+    // Following the system it has to read well in both
+    assert_eq!(Theme::default().bubble_contrast(navy), lifted);
+    let simulated = Theme { simulate: Some(Deficiency::Protanopia), ..light };
+    assert_eq!(simulated.bubble_contrast(navy), best_text_colour(Deficiency::Protanopia.simulate(navy)).1);
+}
+
+#[test]
+fn parses_common_css_formats() {
+    let teal = Rgb(0x00, 0x80, 0x80);
+    assert_eq!("#008080".parse(), Ok(teal));
+    assert_eq!("#008080ff".parse(), Ok(teal));
+    assert_eq!("#0af".parse(), Ok(Rgb(0x00, 0xaa, 0xff)));
+    assert_eq!("#0af8".parse(), Ok(Rgb(0x00, 0xaa, 0xff)));
+    assert_eq!("rgb(0, 128, 128)".parse(), Ok(teal));
+    assert_eq!("rgba(0 128 128 / 50%)".parse(), Ok(teal));
+    assert_eq!("rgb(0%, 100%, 0%)".parse(), Ok(Rgb(0, 255, 0)));
+    assert_eq!("hsl(180, 100%, 25.1%)".parse(), Ok(teal));
+    assert_eq!("hsla(0.5turn 100% 25.1% / 0.3)".parse(), Ok(teal));
+    assert_eq!(" Teal ".parse(), Ok(teal));
+}
+
+#[test]
+fn parse_errors_say_what_went_wrong() {
+    assert_eq!("".parse::<Rgb>(), Err(ParseRgbError::Empty));
+    assert_eq!("#12345".parse::<Rgb>(), Err(ParseRgbError::HexLength(5)));
+    assert_eq!("#12345g".parse::<Rgb>(), Err(ParseRgbError::HexDigit('g')));
+    assert_eq!("rgb(1, 2)".parse::<Rgb>(), Err(ParseRgbError::ArgumentCount { function: "rgb".to_string(), found: 2 }));
+    assert_eq!("rgb(1, 2, 300)".parse::<Rgb>(), Err(ParseRgbError::OutOfRange("300".to_string())));
+    assert_eq!("rgb(1, 2, x)".parse::<Rgb>(), Err(ParseRgbError::InvalidNumber("x".to_string())));
+    assert_eq!("lab(1 2 3)".parse::<Rgb>(), Err(ParseRgbError::UnknownFunction("lab".to_string())));
+    assert_eq!("blurple".parse::<Rgb>(), Err(ParseRgbError::UnknownName("blurple".to_string())));
+}
+
+#[test]
+fn hex_display_round_trips() {
+    let colour = Rgb(0x49, 0x55, 0x65);
+    assert_eq!(colour.to_string(), "#495565");
+    assert_eq!(colour.to_string().parse(), Ok(colour));
+}
+
+#[test]
+fn conversions_round_trip() {
+    for colour in [Rgb(0x49, 0x55, 0x65), Rgb(255, 0, 0), Rgb(0x9a, 0xcd, 0x32), Rgb::WHITE, Rgb::BLACK] {
+        assert_eq!(Rgb::from(Hsl::from(colour)), colour);
+        assert_eq!(Rgb::from(Oklch::from(colour)), colour);
+    }
+    let white = Oklch::from(Rgb::WHITE);
+    assert!((white.l - 1.0).abs() < 1e-3 && white.c < 1e-3);
+}
+
+#[test]
+fn distinct_colour_keeps_away_from_existing_colours() {
+    let existing = [Rgb(0x49, 0x55, 0x65), Rgb(0xd0, 0x40, 0x40)];
+    for seed in 0..10 {
+        let colour = palette::distinct_colour(&existing, seed);
+        let lab = Oklab::from(colour);
+        for other in existing {
+            assert!(lab.distance(Oklab::from(other)) > 0.1, "{colour} is too close to {other}");
+        }
+    }
+}
+
+#[test]
+fn dark_bubbles_are_lifted_off_the_dark_background() {
+    let background = ThemeColours::DARK.surface;
+    for colour in [Rgb(0x30, 0x30, 0x30), Rgb(0x10, 0x10, 0x40), Rgb::BLACK] {
+        let bubble = bubble_colour(colour, true);
+        assert!(bubble.contrast_ratio(background) > colour.contrast_ratio(background), "{colour} became {bubble}");
+        // Black on the dark background is about 1.2:1, too close to see where the bubble ends
+        assert!(bubble.contrast_ratio(background) >= 1.5, "{colour} became {bubble}");
+    }
+    // Light mode and colours already in range are left alone
+    assert_eq!(bubble_colour(Rgb::BLACK, false), Rgb::BLACK);
+    assert_eq!(bubble_colour(Rgb(0x40, 0x90, 0xd0), true), Rgb(0x40, 0x90, 0xd0));
+}
+
+#[test]
+fn system_theme_puts_dark_colours_behind_a_media_query() {
+    let css = Theme::default().css();
+    let (light, dark) = css.split_once("@media (prefers-color-scheme: dark)").unwrap();
+    assert!(light.contains(&format!("--lmt-surface: {}", ThemeColours::LIGHT.surface)));
+    assert!(dark.contains(&format!("--lmt-surface: {}", ThemeColours::DARK.surface)));
+
+    let css = Theme { mode: ThemeMode::Dark, accent: Some(Rgb(0xff, 0x88, 0x00)), ..Theme::default() }.css();
+    assert!(!css.contains("@media"));
+    assert!(css.contains("--lmt-accent: #ff8800"));
+}
+
+#[test]
+fn simulation_leaves_greys_alone() {
+    for deficiency in Deficiency::ALL {
+        assert_eq!(deficiency.simulate(Rgb(128, 128, 128)), Rgb(128, 128, 128));
+    }
+}
+
+#[test]
+fn red_brown_and_olive_are_flagged_for_deuteranopia_only() {
+    let colours = [Rgb(0xb0, 0x60, 0x40), Rgb(0x80, 0x80, 0x30), Rgb(0x30, 0x50, 0xc0)];
+    assert_eq!(Deficiency::Deuteranopia.confusable_pairs(&colours), vec![(0, 1)]);
+    assert!(Deficiency::Tritanopia.confusable_pairs(&colours).is_empty());
+}
@@ -0,0 +1,35 @@
+use let_me_talk::data::{fuzzy_score, Action, Command, CommandUsage};
+use uuid::Uuid;
+
+#[test]
+fn fuzzy_matches_subsequences_and_prefers_word_starts() {
+    assert!(fuzzy_score("nc", "New chat").is_some());
+    assert!(fuzzy_score("xyz", "New chat").is_none());
+    assert!(fuzzy_score("chat", "New chat").unwrap() > fuzzy_score("chat", "Search the archive").unwrap_or(i64::MIN));
+    assert!(fuzzy_score("ex", "Export the chat").unwrap() > fuzzy_score("ex", "Next chat").unwrap());
+}
+
+#[test]
+fn frequently_and_recently_used_commands_rank_first() {
+    let now = 1_700_000_000;
+    let work = Command::OpenChat(Uuid::new_v4());
+    let walk = Command::OpenChat(Uuid::new_v4());
+    let commands = || vec![(work, "Work".to_string()), (walk, "Walk".to_string())];
+
+    let mut usage = CommandUsage::default();
+    assert_eq!(usage.rank("w", commands(), now)[0].0, work);
+
+    usage.record(&walk, now);
+    usage.record(&walk, now);
+    assert_eq!(usage.rank("w", commands(), now)[0].0, walk);
+
+    // A month on, a single fresh use of the other chat wins
+    let later = now + 30 * 24 * 3600;
+    usage.record(&work, later);
+    assert_eq!(usage.rank("w", commands(), later)[0].0, work);
+}
+
+#[test]
+fn command_keys_are_stable() {
+    assert_eq!(Command::Run(Action::NewChat).key(), "action:NewChat");
+}
@@ -0,0 +1,82 @@
+mod harness;
+
+use dioxus::html::input_data::keyboard_types::{Key, Modifiers};
+use harness::Harness;
+use let_me_talk::data::{core_emotion, emotion_summary, ChatData, Personas, CUSTOM_FAMILY};
+use let_me_talk::sync::SyncDoc;
+use uuid::Uuid;
+
+#[test]
+fn tags_use_the_wheels_spelling_and_retagging_changes_the_intensity() {
+    let me = Uuid::new_v4();
+    let mut chat = ChatData::new(me);
+    chat.send(me, "Nobody called back".to_string());
+    let msg = &mut chat.messages.msgs[0];
+    msg.tag_emotion("lonely", 2);
+    msg.tag_emotion("Lonely", 9);
+    msg.tag_emotion("  stuck  ", 0);
+    msg.tag_emotion(" ", 3);
+    let tags: Vec<(&str, u8)> = msg.emotions.iter().map(|tag| (tag.name.as_str(), tag.intensity)).collect();
+    assert_eq!(tags, [("Lonely", 5), ("stuck", 1)]);
+
+    msg.untag_emotion("STUCK");
+    assert_eq!(msg.emotions.len(), 1);
+    assert!(msg.matches("lonely"));
+    assert!(msg.matches("called"));
+    assert!(!msg.matches("stuck"));
+    assert_eq!(core_emotion("lonely"), Some("Sadness"));
+    assert_eq!(core_emotion("stuck"), None);
+}
+
+#[test]
+fn summaries_count_only_the_personas_own_messages() {
+    let critic = Uuid::new_v4();
+    let exile = Uuid::new_v4();
+    let mut chat = ChatData::new(critic);
+    for (persona, text) in [(critic, "Try harder"), (critic, "Not good enough"), (exile, "I'm scared")] {
+        chat.send(persona, text.to_string());
+    }
+    chat.messages.msgs[0].tag_emotion("Frustrated", 4);
+    chat.messages.msgs[1].tag_emotion("Frustrated", 2);
+    chat.messages.msgs[1].tag_emotion("tired", 3);
+    chat.messages.msgs[2].tag_emotion("Scared", 5);
+
+    let summary = emotion_summary(&chat.messages.msgs, critic);
+    assert_eq!(summary.len(), 2);
+    assert_eq!((summary[0].name.as_str(), summary[0].family, summary[0].count), ("Frustrated", "Anger", 2));
+    assert_eq!(summary[0].average_intensity, 3.0);
+    assert_eq!(summary[1].family, CUSTOM_FAMILY);
+}
+
+#[test]
+fn tags_added_after_sending_are_synced_and_exported() {
+    let me = Uuid::new_v4();
+    let mut chat = ChatData::new(me);
+    chat.send(me, "It went fine".to_string());
+    let mut doc = SyncDoc::new();
+    doc.observe(&Personas::starter(), &[chat.clone()]);
+
+    chat.messages.msgs[0].tag_emotion("Relieved", 4);
+    doc.observe(&Personas::starter(), &[chat.clone()]);
+    let mut other = SyncDoc::new();
+    other.merge(&doc);
+    assert_eq!(other.chats()[0].messages.msgs[0].emotions, chat.messages.msgs[0].emotions);
+    assert!(chat.to_markdown(&Personas::starter()).contains("_(feeling Relieved 4)_"));
+
+    // Messages saved before tagging have none
+    let json = serde_json::to_string(&chat).unwrap();
+    let older = json.replace(r#","emotions":[{"name":"Relieved","intensity":4}]"#, "");
+    assert_ne!(older, json);
+    assert!(serde_json::from_str::<ChatData>(&older).unwrap().messages.msgs[0].emotions.is_empty());
+}
+
+#[test]
+fn feelings_opens_the_picker_under_a_message() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    harness.type_text("I can't sleep");
+    harness.press(Key::Enter, Modifiers::empty());
+    assert!(!harness.html().contains("Your own word for it"));
+    harness.click_text("Feelings");
+    assert!(harness.html().contains("Your own word for it"));
+}
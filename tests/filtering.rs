@@ -0,0 +1,96 @@
+mod harness;
+
+use chrono::TimeZone;
+use harness::Harness;
+use let_me_talk::data::{Chat, ChatFilter, ChatSort, Library, MessageKind};
+use uuid::Uuid;
+
+/// Three chats named as given, in the order they were made
+fn named_chats(harness: &mut Harness, names: [&str; 3]) -> Vec<Chat> {
+    names
+        .into_iter()
+        .map(|name| {
+            harness.click_text("New Chat");
+            let chat = harness.active_chat();
+            chat.name.set(name.to_string());
+            chat
+        })
+        .collect()
+}
+
+fn millis(year: i32, month: u32, day: u32) -> i64 {
+    chrono::Local.with_ymd_and_hms(year, month, day, 12, 0, 0).unwrap().timestamp_millis()
+}
+
+#[test]
+fn the_filter_narrows_what_is_shown_and_cycled_through() {
+    let mut harness = Harness::new();
+    let made = named_chats(&mut harness, ["Morning check-in", "Argument with Sam", "Evening check-in"]);
+    let uuids: Vec<Uuid> = made.iter().map(|chat| *chat.uuid()).collect();
+    let chats = harness.state().chats;
+
+    chats.write().library.filter.text = "CHECK".to_string();
+    assert_eq!(chats.read().shown(), [uuids[0], uuids[2]]);
+    // The active chat is the last one made, the next one shown wraps around past the hidden one
+    assert_eq!(chats.read().cycle_chat(false), Some(uuids[0]));
+    assert_eq!(chats.read().cycle_chat(true), Some(uuids[0]));
+
+    let critic = Uuid::new_v4();
+    made[2].added_personas.write().insert(critic);
+    chats.write().library.filter.persona = Some(critic);
+    assert_eq!(chats.read().shown(), [uuids[2]]);
+
+    chats.write().library.filter = ChatFilter {
+        tag: Some("work".to_string()),
+        ..Default::default()
+    };
+    chats.write().library.filing_mut(uuids[1]).tags.insert("Work".to_string());
+    assert_eq!(chats.read().shown(), [uuids[1]]);
+}
+
+#[test]
+fn sorting_orders_each_group() {
+    let mut harness = Harness::new();
+    let made = named_chats(&mut harness, ["banana", "Apple", "cherry"]);
+    let uuids: Vec<Uuid> = made.iter().map(|chat| *chat.uuid()).collect();
+    let me = *made[0].active_persona.read();
+    made[2].post(MessageKind::Speech, me, "one".to_string());
+    made[2].post(MessageKind::Speech, me, "two".to_string());
+    made[0].post(MessageKind::Speech, me, "one".to_string());
+    let chats = harness.state().chats;
+
+    assert_eq!(chats.read().shown(), uuids);
+    chats.write().library.sort = ChatSort::Name;
+    assert_eq!(chats.read().shown(), [uuids[1], uuids[0], uuids[2]]);
+    chats.write().library.sort = ChatSort::MessageCount;
+    assert_eq!(chats.read().shown(), [uuids[2], uuids[0], uuids[1]]);
+    chats.write().library.sort = ChatSort::LastActivity;
+    assert_eq!(chats.read().shown()[0], uuids[0]);
+}
+
+#[test]
+fn a_date_range_keeps_chats_active_within_it() {
+    let filter = ChatFilter {
+        from: Some("2024-03-10".to_string()),
+        to: Some("2024-03-20".to_string()),
+        ..Default::default()
+    };
+    assert!(filter.in_range(millis(2024, 3, 1), millis(2024, 3, 10)));
+    assert!(filter.in_range(millis(2024, 3, 20), millis(2024, 4, 1)));
+    assert!(!filter.in_range(millis(2024, 3, 1), millis(2024, 3, 9)));
+    assert!(!filter.in_range(millis(2024, 3, 21), millis(2024, 3, 22)));
+}
+
+#[test]
+fn the_filter_and_sort_are_saved_with_the_library() {
+    let mut library = Library::default();
+    library.filter.text = "check".to_string();
+    library.sort = ChatSort::Created;
+    let json = serde_json::to_string(&library).unwrap();
+    assert_eq!(serde_json::from_str::<Library>(&json).unwrap(), library);
+
+    // Libraries saved before there was a filter load unfiltered
+    let older: Library = serde_json::from_str(r#"{"folders":{},"filing":{}}"#).unwrap();
+    assert!(older.filter.is_empty());
+    assert_eq!(older.sort, ChatSort::Manual);
+}
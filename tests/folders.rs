@@ -0,0 +1,78 @@
+mod harness;
+
+use harness::Harness;
+use let_me_talk::data::{parse_tags, Chat, Chats, Dragged, DropTarget, Library};
+use uuid::Uuid;
+
+#[test]
+fn tags_are_trimmed_and_kept_once() {
+    let tags: Vec<String> = parse_tags(" work, Family,, work ,WORK, grief ").into_iter().collect();
+    assert_eq!(tags, ["work", "Family", "grief"]);
+}
+
+#[test]
+fn deleting_a_folder_moves_its_contents_up() {
+    let mut library = Library::default();
+    let work = library.new_folder("Work".to_string(), None);
+    let conflicts = library.new_folder("Conflicts".to_string(), Some(work));
+    let inner = library.new_folder("Boss".to_string(), Some(conflicts));
+    let chat = Uuid::new_v4();
+    library.file(chat, Some(conflicts));
+    assert_eq!(library.path(&inner), "Work / Conflicts / Boss");
+
+    library.delete_folder(&conflicts);
+    assert_eq!(library.filing(&chat).folder, Some(work));
+    assert_eq!(library.folders[&inner].parent, Some(work));
+}
+
+#[test]
+fn a_folder_cannot_go_inside_itself() {
+    let mut library = Library::default();
+    let outer = library.new_folder("Outer".to_string(), None);
+    let inner = library.new_folder("Inner".to_string(), Some(outer));
+    assert!(!library.move_folder(outer, Some(inner)));
+    assert!(!library.move_folder(outer, Some(outer)));
+    assert_eq!(library.folders[&outer].parent, None);
+    assert!(library.move_folder(inner, None));
+    assert!(library.move_folder(outer, Some(inner)));
+}
+
+#[test]
+fn dropping_chats_files_pins_and_reorders_them() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    let first = *harness.active_chat().uuid();
+    harness.click_text("New Chat");
+    let second = *harness.active_chat().uuid();
+    harness.click_text("New Chat");
+    let third = *harness.active_chat().uuid();
+    let chats = harness.state().chats;
+
+    let folder = chats.write().library.new_folder("Parts".to_string(), None);
+    chats.write().refile(Dragged::Chat(second), DropTarget::Folder(Some(folder)));
+    chats.write().refile(Dragged::Chat(third), DropTarget::Pinned);
+    // Dropping on a chat puts it before that one, in the same folder
+    chats.write().refile(Dragged::Chat(first), DropTarget::Chat(second));
+
+    let uuids = |list: Vec<(Chat, usize)>| -> Vec<Uuid> {
+        list.into_iter().map(|(chat, _)| *chat.uuid()).collect()
+    };
+    let chats = chats.read();
+    assert_eq!(uuids(chats.filed_in(Some(folder))), [first, second]);
+    assert!(chats.filed_in(None).is_empty());
+    // The sidebar groups every folder at once, pinned chats stay out of it
+    let by_folder = chats.by_folder();
+    assert_eq!(by_folder.keys().copied().collect::<Vec<_>>(), [Some(folder)]);
+    assert_eq!(uuids(by_folder[&Some(folder)].clone()), [first, second]);
+    let pinned: Vec<Uuid> = chats.pinned().iter().map(|chat| *chat.uuid()).collect();
+    assert_eq!(pinned, [third]);
+}
+
+#[test]
+fn chats_saved_before_folders_load_unfiled() {
+    let uuid = Uuid::new_v4();
+    let json = format!(r#"{{"chat_ids":["{uuid}"],"chats":[],"active_chat":null,"save_toggle":false}}"#);
+    let chats: Chats = serde_json::from_str(&json).unwrap();
+    assert_eq!(chats.library, Library::default());
+    assert_eq!(chats.library.filing(&uuid).folder, None);
+}
@@ -0,0 +1,76 @@
+mod harness;
+
+use dioxus::html::input_data::keyboard_types::{Key, Modifiers};
+use harness::Harness;
+use let_me_talk::data::{Guide, Guides, MessageKind};
+use let_me_talk::storage;
+
+#[test]
+fn scripts_can_hold_one_guide_or_several() {
+    let one = r#"{"name": "Check-in", "steps": [{"title": "Arrive", "prompt": "Who's here?"}]}"#;
+    let guides = Guide::parse(one).unwrap();
+    assert_eq!(guides[0].steps[0].answer_as, "");
+    assert_eq!(guides[0].description, "");
+
+    let many = serde_json::to_string(&vec![Guide::six_fs(), guides[0].clone()]).unwrap();
+    assert_eq!(Guide::parse(&many).unwrap().len(), 2);
+
+    assert!(Guide::parse(r#"{"name": "Empty", "steps": []}"#).is_err());
+    assert!(Guide::parse("not json").is_err());
+}
+
+#[test]
+fn importing_replaces_guides_with_the_same_name() {
+    let mut guides = Guides::default();
+    let mut guide = Guide::parse(r#"{"name": "Check-in", "steps": [{"title": "Arrive", "prompt": "Who's here?"}]}"#)
+        .unwrap()
+        .remove(0);
+    guides.import(vec![guide.clone()]);
+    guide.steps[0].prompt = "Who's around?".to_string();
+    guides.import(vec![guide]);
+    assert_eq!(guides.0.len(), 1);
+    assert_eq!(guides.find("check-in").unwrap().steps[0].prompt, "Who's around?");
+    // The built in ones are always there
+    assert_eq!(guides.all()[0], Guide::six_fs());
+}
+
+#[test]
+fn a_guided_session_posts_each_prompt_and_hands_over_to_its_persona() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    let chat = harness.active_chat();
+    let me = *chat.active_persona.read();
+
+    harness.click_text("Guide");
+    harness.click_text("The 6 F's");
+    let first = chat.messages.read().msgs[0].clone();
+    assert_eq!(first.kind, MessageKind::Narration);
+    assert!(first.msg.starts_with("Find: "));
+    // The first step is answered by Me, who is already speaking
+    assert_eq!(*chat.active_persona.read(), me);
+
+    harness.type_text("/guide next");
+    harness.press(Key::Enter, Modifiers::empty());
+    assert_eq!(chat.session.read().as_ref().unwrap().step, 1);
+    assert!(chat.messages.read().msgs[1].msg.starts_with("Focus: "));
+
+    harness.type_text("/guide end");
+    harness.press(Key::Enter, Modifiers::empty());
+    assert!(chat.session.read().is_none());
+    let last = chat.messages.read().msgs.last().unwrap().clone();
+    assert_eq!(last.kind, MessageKind::Event);
+    assert_eq!(last.msg, "End of The 6 F's");
+}
+
+#[test]
+fn imported_guides_are_offered_in_the_chat_straight_away() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    let imported = Guide::parse(r#"{"name": "Check-in", "steps": [{"title": "Arrive", "prompt": "Who's here?"}]}"#).unwrap();
+    harness.state().guides.write().import(imported);
+
+    harness.click_text("Guide");
+    assert!(harness.html().contains("Check-in"));
+    let saved: Guides = storage::try_retrieve("ifs_guides").unwrap();
+    assert_eq!(saved.0[0].name, "Check-in");
+}
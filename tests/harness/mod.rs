@@ -0,0 +1,247 @@
+//! Renders the app headlessly against in-memory storage and drives it through
+//! the same event listeners the browser would call.
+use dioxus::html::input_data::keyboard_types::{Code, Key, Location, Modifiers};
+use dioxus::html::prelude::{EvalError, EvalProvider, Evaluator};
+use dioxus::html::{FormData, KeyboardData, MouseData};
+use dioxus::prelude::*;
+use dioxus::core::{BorrowedAttributeValue, ElementId, Mutation, Mutations, Template, TemplateNode};
+use let_me_talk::{app::Layout, data::*, storage};
+use std::{any::Any, cell::RefCell, collections::HashMap, future::Future, pin::Pin, rc::Rc};
+
+/// Swallows the JavaScript the components run for focus and resizing
+struct NoEval;
+
+impl EvalProvider for NoEval {
+    fn new_evaluator(&self, _js: String) -> Result<Rc<dyn Evaluator>, EvalError> {
+        Ok(Rc::new(NoEval))
+    }
+}
+
+impl Evaluator for NoEval {
+    fn send(&self, _data: serde_json::Value) -> Result<(), EvalError> {
+        Ok(())
+    }
+
+    fn recv(&self) -> Pin<Box<dyn Future<Output = Result<serde_json::Value, EvalError>>>> {
+        Box::pin(std::future::pending())
+    }
+
+    fn join(&self) -> Pin<Box<dyn Future<Output = Result<serde_json::Value, EvalError>>>> {
+        Box::pin(async { Ok(serde_json::Value::Null) })
+    }
+}
+
+struct RootProps {
+    state: Rc<RefCell<Option<AppState>>>,
+}
+
+fn root(cx: Scope<RootProps>) -> Element {
+    use_context_provider(cx, || Rc::new(NoEval) as Rc<dyn EvalProvider>);
+    AppState::load(cx);
+    *cx.props.state.borrow_mut() = Some(AppState::use_app_context(cx));
+    cx.render(rsx! { Layout {} })
+}
+
+/// What we know about a mounted element that has a listener or dynamic attribute
+#[derive(Default)]
+struct Mounted {
+    /// The static text directly inside the element, from its template
+    text: String,
+    attrs: HashMap<String, String>,
+    listeners: Vec<String>,
+    /// The element it was mounted under, or the root of its template, so removing an
+    /// element can forget everything inside it too
+    parent: Option<ElementId>,
+}
+
+pub struct Harness {
+    vdom: VirtualDom,
+    state: Rc<RefCell<Option<AppState>>>,
+    templates: HashMap<&'static str, Template<'static>>,
+    elements: HashMap<ElementId, Mounted>,
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        storage::use_memory_backend();
+        let state = Rc::new(RefCell::new(None));
+        let mut vdom = VirtualDom::new_with_props(root, RootProps { state: state.clone() });
+        let mut templates = HashMap::new();
+        let mut elements = HashMap::new();
+        track(&mut templates, &mut elements, vdom.rebuild());
+        Harness { vdom, state, templates, elements }
+    }
+
+    pub fn state(&self) -> AppState {
+        let state = *self.state.borrow();
+        state.expect("app state is loaded on the first render")
+    }
+
+    pub fn active_chat(&self) -> Chat {
+        let chat = *self.state().active_chat.read();
+        chat.expect("a chat is open")
+    }
+
+    pub fn html(&self) -> String {
+        dioxus_ssr::render(&self.vdom)
+    }
+
+    fn find(&self, listener: &str, matches: impl Fn(&Mounted) -> bool) -> ElementId {
+        *self
+            .elements
+            .iter()
+            .find(|(_, element)| element.listeners.iter().any(|l| l == listener) && matches(element))
+            .unwrap_or_else(|| panic!("no element listening for {listener} matched"))
+            .0
+    }
+
+    fn dispatch(&mut self, name: &str, data: Rc<dyn Any>, element: ElementId) {
+        self.vdom.handle_event(name, data, element, true);
+        track(&mut self.templates, &mut self.elements, self.vdom.render_immediate());
+    }
+
+    fn message_input(&self) -> ElementId {
+        self.find("input", |element| element.attrs.get("id").is_some_and(|id| id.starts_with("mi_")))
+    }
+
+    /// Sets the message input's contents, as the browser does on every keystroke
+    pub fn type_text(&mut self, text: &str) {
+        let input = self.message_input();
+        let data = FormData {
+            value: text.to_string(),
+            values: Default::default(),
+            files: None,
+        };
+        self.dispatch("input", Rc::new(data), input);
+    }
+
+    /// Presses a key in the message input, anything it doesn't handle bubbles up to the app's shortcuts
+    pub fn press(&mut self, key: Key, modifiers: Modifiers) {
+        let input = self.message_input();
+        let code = match key {
+            Key::Enter => Code::Enter,
+            Key::Tab => Code::Tab,
+            _ => Code::Unidentified,
+        };
+        let data = KeyboardData::new(key, code, Location::Standard, false, modifiers);
+        self.dispatch("keydown", Rc::new(data), input);
+    }
+
+    /// Clicks the element whose own static text is `text`
+    pub fn click_text(&mut self, text: &str) {
+        let button = self.find("click", |element| element.text == text);
+        self.dispatch("click", Rc::new(MouseData::default()), button);
+    }
+
+    /// Clicks the element whose title, when it's worked out from state, is `title`
+    pub fn click_title(&mut self, title: &str) {
+        let button = self.find("click", |element| element.attrs.get("title").is_some_and(|t| t == title));
+        self.dispatch("click", Rc::new(MouseData::default()), button);
+    }
+}
+
+/// Records which element ids have listeners and what they look like, so events can be targeted
+fn track(
+    templates: &mut HashMap<&'static str, Template<'static>>,
+    elements: &mut HashMap<ElementId, Mounted>,
+    mutations: Mutations<'_>,
+) {
+    for template in mutations.templates {
+        templates.insert(template.name, template);
+    }
+    let mut root: Option<(&'static TemplateNode<'static>, ElementId)> = None;
+    // Nodes created but not yet placed in the page, as the renderer keeps them
+    let mut stack: Vec<ElementId> = Vec::new();
+    for edit in mutations.edits {
+        match edit {
+            Mutation::LoadTemplate { name, index, id } => {
+                let roots = templates[name].roots;
+                let node = &roots[index];
+                root = Some((node, id));
+                stack.push(id);
+                elements.insert(id, Mounted { text: static_text(node), ..Default::default() });
+            }
+            Mutation::AssignId { path, id } => {
+                let (root, root_id) = root.unwrap();
+                let node = path.iter().fold(root, |node, i| match node {
+                    TemplateNode::Element { children, .. } => &children[*i as usize],
+                    _ => node,
+                });
+                elements.insert(id, Mounted { text: static_text(node), parent: Some(root_id), ..Default::default() });
+            }
+            Mutation::CreatePlaceholder { id } | Mutation::CreateTextNode { id, .. } | Mutation::PushRoot { id } => {
+                elements.insert(id, Mounted::default());
+                stack.push(id);
+            }
+            Mutation::AppendChildren { id, m } => place(elements, &mut stack, m, Some(id)),
+            Mutation::ReplacePlaceholder { m, .. } => {
+                let nodes = stack.split_off(stack.len().saturating_sub(m));
+                let parent = stack.last().copied();
+                stack.extend(nodes);
+                place(elements, &mut stack, m, parent);
+            }
+            Mutation::InsertAfter { id, m } | Mutation::InsertBefore { id, m } => {
+                let parent = elements.get(&id).and_then(|element| element.parent);
+                place(elements, &mut stack, m, parent);
+            }
+            Mutation::ReplaceWith { id, m } => {
+                let parent = elements.get(&id).and_then(|element| element.parent);
+                place(elements, &mut stack, m, parent);
+                forget(elements, id);
+            }
+            Mutation::Remove { id } => forget(elements, id),
+            Mutation::SetAttribute { name, value: BorrowedAttributeValue::Text(value), id, .. } => {
+                elements.entry(id).or_default().attrs.insert(name.to_string(), value.to_string());
+            }
+            Mutation::NewEventListener { name, id } => {
+                elements.entry(id).or_default().listeners.push(name.to_string());
+            }
+            Mutation::RemoveEventListener { name, id } => {
+                if let Some(element) = elements.get_mut(&id) {
+                    element.listeners.retain(|listener| listener.as_str() != name);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Takes the top `m` nodes off the stack as they're put into the page under `parent`
+fn place(elements: &mut HashMap<ElementId, Mounted>, stack: &mut Vec<ElementId>, m: usize, parent: Option<ElementId>) {
+    for id in stack.split_off(stack.len().saturating_sub(m)) {
+        elements.entry(id).or_default().parent = parent;
+    }
+}
+
+/// Drops a removed element and everything that was inside it, their ids get reused
+fn forget(elements: &mut HashMap<ElementId, Mounted>, id: ElementId) {
+    let mut removed = vec![id];
+    loop {
+        let inside: Vec<ElementId> = elements
+            .iter()
+            .filter(|(child, element)| !removed.contains(child) && element.parent.is_some_and(|parent| removed.contains(&parent)))
+            .map(|(child, _)| *child)
+            .collect();
+        if inside.is_empty() {
+            break;
+        }
+        removed.extend(inside);
+    }
+    for id in removed {
+        elements.remove(&id);
+    }
+}
+
+fn static_text(node: &TemplateNode) -> String {
+    match node {
+        TemplateNode::Element { children, .. } => children
+            .iter()
+            .filter_map(|child| match child {
+                TemplateNode::Text { text } => Some(*text),
+                _ => None,
+            })
+            .collect(),
+        TemplateNode::Text { text } => text.to_string(),
+        _ => String::new(),
+    }
+}
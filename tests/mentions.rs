@@ -0,0 +1,71 @@
+mod harness;
+
+use dioxus::html::input_data::keyboard_types::{Key, Modifiers};
+use harness::Harness;
+use let_me_talk::colours::Rgb;
+use let_me_talk::data::{Persona, Personas};
+
+fn persona(name: &str) -> Persona {
+    Persona {
+        name: name.to_string(),
+        colour: Rgb(0xaa, 0x22, 0x33),
+    }
+}
+
+#[test]
+fn mentions_match_whole_names_ignoring_case_and_prefer_the_longest() {
+    let mut personas = Personas::starter();
+    let inner = personas.push(persona("Inner"));
+    let critic = personas.push(persona("Inner Critic"));
+
+    assert_eq!(personas.parse_mention("@inner critic you again"), Some((critic, "you again")));
+    assert_eq!(personas.parse_mention("@Inner hi"), Some((inner, "hi")));
+    assert_eq!(personas.parse_mention("@Inner"), Some((inner, "")));
+    assert_eq!(personas.parse_mention("@Innerly hi"), None);
+    assert_eq!(personas.parse_mention("hi @Inner"), None);
+}
+
+#[test]
+fn mentioning_a_persona_sends_as_them_and_adds_them_to_the_chat() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    let critic = harness.state().personas.write().push(persona("Critic"));
+    let chat = harness.active_chat();
+    assert!(!chat.added_personas.read().contains(&critic));
+
+    harness.type_text("@critic not again");
+    harness.press(Key::Enter, Modifiers::empty());
+
+    let messages = chat.messages.read();
+    assert_eq!(messages.msgs[0].msg, "not again");
+    assert_eq!(messages.msgs[0].persona, critic);
+    assert!(chat.added_personas.read().contains(&critic));
+    assert_eq!(*chat.active_persona.read(), critic);
+}
+
+#[test]
+fn tab_completes_a_partly_typed_mention() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    harness.state().personas.write().push(persona("Critic"));
+
+    harness.type_text("@cri");
+    assert!(harness.html().contains("add to chat"));
+    harness.press(Key::Tab, Modifiers::empty());
+
+    assert_eq!(*harness.active_chat().current_message.read(), "@Critic ");
+    assert!(!harness.html().contains("add to chat"));
+}
+
+#[test]
+fn the_mention_popup_shows_persona_markers() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    harness.state().personas.write().push(persona("Critic"));
+    harness.state().settings.write().theme.markers = true;
+
+    // The second persona's shape, already in the add persona dialog
+    let before = harness.html().matches('▲').count();
+    harness.type_text("@cri");
+    assert_eq!(harness.html().matches('▲').count(), before + 1);
+}
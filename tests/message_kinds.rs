@@ -0,0 +1,52 @@
+mod harness;
+
+use dioxus::html::input_data::keyboard_types::{Key, Modifiers};
+use harness::Harness;
+use let_me_talk::data::{ChatData, Message, MessageKind, Personas};
+
+#[test]
+fn messages_saved_before_kinds_are_speech() {
+    let uuid = uuid::Uuid::new_v4();
+    let json = format!(r#"{{"uuid":"{uuid}","msg":"hi","persona":"{uuid}"}}"#);
+    let msg: Message = serde_json::from_str(&json).unwrap();
+    assert_eq!(msg.kind, MessageKind::Speech);
+}
+
+#[test]
+fn markdown_tells_the_kinds_apart() {
+    let personas = Personas::starter();
+    let me = *personas.get_index(0).unwrap().0;
+    let mut chat = ChatData::new(me);
+    chat.name = "Session".to_string();
+    chat.post(MessageKind::Narration, me, "A quiet room".to_string());
+    chat.send(me, "Hello".to_string());
+    chat.post(MessageKind::Aside, me, "tightness in chest".to_string());
+    chat.post(MessageKind::Event, me, "10m timer is up".to_string());
+
+    let name = &personas.get(&me).unwrap().name;
+    let markdown = chat.to_markdown(&personas);
+    assert!(markdown.contains("_A quiet room_\n"));
+    assert!(markdown.contains(&format!("**{name}**: Hello\n")));
+    assert!(markdown.contains(&format!("**{name}** _(aside)_: _tightness in chest_\n")));
+    assert!(markdown.contains("> 10m timer is up\n"));
+    assert_eq!(chat.messages.msgs[2].speaker(&personas), format!("{name} (aside)"));
+    assert_eq!(chat.messages.msgs[0].speaker(&personas), "Narration");
+}
+
+#[test]
+fn asides_and_events_are_written_from_the_input() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    let chat = harness.active_chat();
+
+    harness.type_text("/aside tightness in chest");
+    harness.press(Key::Enter, Modifiers::empty());
+    harness.type_text("/event Took a break");
+    harness.press(Key::Enter, Modifiers::empty());
+    harness.type_text("Back again");
+    harness.press(Key::Enter, Modifiers::empty());
+
+    let kinds: Vec<MessageKind> = chat.messages.read().msgs.iter().map(|msg| msg.kind).collect();
+    assert_eq!(kinds, [MessageKind::Aside, MessageKind::Event, MessageKind::Speech]);
+    assert!(harness.html().contains("Took a break"));
+}
@@ -0,0 +1,117 @@
+mod harness;
+
+use harness::Harness;
+use indexmap::IndexSet;
+use let_me_talk::data::{copy_messages, merge_messages, ChatData, MergeOrder, Message, MessageKind};
+use let_me_talk::storage;
+use uuid::Uuid;
+
+fn message(persona: Uuid, msg: &str, sent: i64) -> Message {
+    Message {
+        sent,
+        ..Message::new(MessageKind::Speech, persona, msg.to_string())
+    }
+}
+
+fn texts(msgs: &[Message]) -> Vec<&str> {
+    msgs.iter().map(|msg| msg.msg.as_str()).collect()
+}
+
+#[test]
+fn interleaving_goes_by_send_time_and_keeps_each_side_in_order() {
+    let me = Uuid::new_v4();
+    let ours = vec![message(me, "a1", 10), message(me, "a2", 0), message(me, "a3", 30)];
+    let theirs = vec![message(me, "b1", 5), message(me, "b2", 20)];
+
+    let merged = merge_messages(ours.clone(), theirs.clone(), MergeOrder::Interleave);
+    // a2 has no time so it stays right after a1
+    assert_eq!(texts(&merged), ["b1", "a1", "a2", "b2", "a3"]);
+
+    let appended = merge_messages(ours, theirs, MergeOrder::Append);
+    assert_eq!(texts(&appended), ["a1", "a2", "a3", "b1", "b2"]);
+}
+
+#[test]
+fn merging_a_branch_back_keeps_shared_messages_once() {
+    let mut chat = ChatData::new(Uuid::new_v4());
+    chat.send(chat.active_persona, "shared".to_string());
+    let mut branch = chat.branch_from(&chat.messages.msgs[0].uuid).unwrap();
+    branch.send(branch.active_persona, "only in the branch".to_string());
+
+    let merged = merge_messages(chat.messages.msgs, branch.messages.msgs, MergeOrder::Interleave);
+    assert_eq!(texts(&merged), ["shared", "only in the branch"]);
+}
+
+#[test]
+fn moved_messages_keep_their_ids_and_bring_their_personas() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    let to = harness.active_chat();
+    harness.click_text("New Chat");
+    let from = harness.active_chat();
+    let me = *from.active_persona.read();
+    let critic = Uuid::new_v4();
+    from.post(MessageKind::Speech, me, "stay".to_string());
+    from.post(MessageKind::Speech, critic, "go".to_string());
+    let first = from.messages.read().msgs[0].uuid;
+    let go = from.messages.read().msgs[1].uuid;
+    from.messages.write().msgs[1].reply_to = Some(first);
+    from.post(MessageKind::Speech, me, "go too".to_string());
+    let also = from.messages.read().msgs[2].uuid;
+    from.messages.write().msgs[2].reply_to = Some(go);
+    from.post(MessageKind::Speech, me, "answer left behind".to_string());
+    from.messages.write().msgs[3].reply_to = Some(go);
+
+    let picked: IndexSet<Uuid> = [go, also].into_iter().collect();
+    to.receive(from.take_messages(&picked));
+
+    assert_eq!(texts(&from.messages.read().msgs), ["stay", "answer left behind"]);
+    let moved = to.messages.read().msgs.clone();
+    assert_eq!(texts(&moved), ["go", "go too"]);
+    assert_eq!((moved[0].uuid, moved[1].uuid), (go, also));
+    // The reply to a message left behind is dropped, the one between moved messages is kept
+    assert_eq!(moved[0].reply_to, None);
+    assert_eq!(moved[1].reply_to, Some(go));
+    // A reply left behind still names the moved message, which is found where it went
+    let left = from.messages.read().msgs[1].reply_to.unwrap();
+    assert_eq!(to.messages.read().get(&left).map(|msg| msg.msg.clone()), Some("go".to_string()));
+    assert!(to.added_personas.read().contains(&critic));
+}
+
+#[test]
+fn copied_messages_get_new_ids_and_follow_their_replies() {
+    let me = Uuid::new_v4();
+    let first = message(me, "first", 0);
+    let reply = Message {
+        reply_to: Some(first.uuid),
+        ..message(me, "reply", 0)
+    };
+
+    let copied = copy_messages(&[first.clone(), reply.clone()]);
+    assert!(copied[0].uuid != first.uuid && copied[1].uuid != reply.uuid);
+    assert_eq!(copied[1].reply_to, Some(copied[0].uuid));
+}
+
+#[test]
+fn merging_through_the_dialog_folds_the_chat_in_and_drops_it() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    let from = harness.active_chat();
+    let critic = Uuid::new_v4();
+    from.name.set("Old session".to_string());
+    from.add_persona(critic);
+    from.post(MessageKind::Speech, critic, "from the old one".to_string());
+    harness.click_text("New Chat");
+    let into = harness.active_chat();
+    into.post(MessageKind::Speech, *into.active_persona.read(), "already here".to_string());
+
+    harness.click_title("Merge Old session into this chat");
+
+    assert!(!harness.state().chats.read().chat_ids().contains(from.uuid()));
+    assert!(into.added_personas.read().contains(&critic));
+    let saved: ChatData = storage::try_retrieve(ChatData::storage_key(into.uuid())).unwrap();
+    assert_eq!(saved.messages.msgs.len(), 2);
+    assert!(saved.added_personas.contains(&critic));
+    let source: ChatData = storage::try_retrieve(ChatData::storage_key(from.uuid())).unwrap();
+    assert_eq!(texts(&source.messages.msgs), ["from the old one"]);
+}
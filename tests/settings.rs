@@ -0,0 +1,80 @@
+mod harness;
+
+use dioxus::html::input_data::keyboard_types::{Key, Modifiers};
+use harness::Harness;
+use let_me_talk::colours::Rgb;
+use let_me_talk::data::{Action, ChatData, Keymap, Persona, Settings, Shortcut, DEFAULT_DATE_FORMAT};
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[test]
+fn ctrl_enter_sends_when_chosen() {
+    let mut harness = Harness::new();
+    let mut settings = harness.state().settings.write();
+    settings.keymap.bind(Action::Send, Shortcut::from_str("Ctrl+Enter").unwrap());
+    settings.keymap.bind(Action::Newline, Shortcut::key("Enter"));
+    drop(settings);
+    harness.click_text("New Chat");
+
+    harness.type_text("first line");
+    harness.press(Key::Enter, Modifiers::empty());
+    assert!(harness.active_chat().messages.read().msgs.is_empty());
+
+    // The newline itself goes in at the caret through the browser, as it would have typed it
+    harness.type_text("first line\nsecond");
+    harness.press(Key::Enter, Modifiers::CONTROL);
+    assert_eq!(harness.active_chat().messages.read().msgs[0].msg, "first line\nsecond");
+}
+
+#[test]
+fn new_chats_start_with_the_default_personas() {
+    let mut harness = Harness::new();
+    let state = harness.state();
+    let critic = state.personas.write().push(Persona {
+        name: "Critic".to_string(),
+        colour: Rgb(0xaa, 0x22, 0x33),
+    });
+    let mut settings = state.settings.write();
+    settings.default_personas.insert(critic);
+    settings.date_format = "Session %Y".to_string();
+    drop(settings);
+
+    harness.click_text("New Chat");
+
+    let chat = harness.active_chat();
+    assert_eq!(*chat.active_persona.read(), critic);
+    assert_eq!(chat.added_personas.read().len(), 1);
+    assert!(chat.name.read().starts_with("Session 2"));
+}
+
+#[test]
+fn invalid_date_formats_fall_back_to_the_default() {
+    let settings = Settings {
+        date_format: "%Q nonsense".to_string(),
+        ..Default::default()
+    };
+    let expected = chrono::Local::now().format(DEFAULT_DATE_FORMAT).to_string();
+    assert_eq!(settings.chat_name(), expected);
+    // Chats made without settings are named on the same clock
+    assert_eq!(ChatData::new(Uuid::new_v4()).name, expected);
+}
+
+#[test]
+fn shortcuts_added_since_the_keymap_was_saved_get_their_defaults() {
+    // Saved before the command palette and chat filter existed
+    let mut old = Keymap::default();
+    old.bindings.retain(|binding| !matches!(binding.action, Action::CommandPalette | Action::FilterChats));
+    let mut json = serde_json::to_value(Settings::default()).unwrap();
+    json["keymap"] = serde_json::to_value(&old.bindings).unwrap();
+    let settings: Settings = serde_json::from_value(json).unwrap();
+    let keymap = &settings.keymap;
+    assert_eq!(keymap.action(&Key::Character("k".to_string()), Modifiers::CONTROL), Some(Action::CommandPalette));
+    assert_eq!(keymap.action(&Key::Character("f".to_string()), Modifiers::ALT), Some(Action::FilterChats));
+
+    // Ones cleared since stay cleared
+    let mut keymap = Keymap::default();
+    keymap.unbind(Action::CommandPalette);
+    let json = serde_json::to_string(&keymap).unwrap();
+    let keymap: Keymap = serde_json::from_str(&json).unwrap();
+    assert_eq!(keymap.shortcut(Action::CommandPalette), None);
+}
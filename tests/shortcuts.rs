@@ -0,0 +1,114 @@
+mod harness;
+
+use dioxus::html::input_data::keyboard_types::{Key, Modifiers};
+use harness::Harness;
+use let_me_talk::colours::Rgb;
+use let_me_talk::data::{Action, Keymap, Persona, Shortcut};
+use std::str::FromStr;
+
+fn with_chat() -> Harness {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    harness
+}
+
+#[test]
+fn shortcuts_parse_and_display() {
+    for text in ["Enter", "Shift+Tab", "Ctrl+Alt+ArrowUp", "Ctrl++", "?"] {
+        assert_eq!(Shortcut::from_str(text).unwrap().to_string(), text);
+    }
+    assert_eq!(Shortcut::from_str("cmd+k").unwrap().to_string(), "Meta+k");
+    assert!(Shortcut::from_str("Hyper+k").is_err());
+}
+
+#[test]
+fn characters_match_whatever_shift_the_layout_needs() {
+    let keymap = Keymap::default();
+    let question = Key::Character("?".to_string());
+    assert_eq!(keymap.action(&question, Modifiers::SHIFT), Some(Action::ShowShortcuts));
+    assert_eq!(keymap.action(&Key::Tab, Modifiers::SHIFT), Some(Action::PreviousPersona));
+    assert_eq!(keymap.action(&Key::Character("2".to_string()), Modifiers::ALT), Some(Action::SelectPersona(2)));
+}
+
+#[test]
+fn alt_number_selects_a_persona() {
+    let mut harness = with_chat();
+    let chat = harness.active_chat();
+    let me = *chat.active_persona.read();
+    let critic = harness.state().personas.write().push(Persona {
+        name: "Critic".to_string(),
+        colour: Rgb(0xaa, 0x22, 0x33),
+    });
+    chat.add_persona(critic);
+
+    harness.press(Key::Character("1".to_string()), Modifiers::ALT);
+    assert_eq!(*chat.active_persona.read(), me);
+    harness.press(Key::Character("2".to_string()), Modifiers::ALT);
+    assert_eq!(*chat.active_persona.read(), critic);
+}
+
+#[test]
+fn alt_arrows_move_between_chats() {
+    let mut harness = with_chat();
+    let first = *harness.active_chat().uuid();
+    harness.click_text("New Chat");
+    let second = *harness.active_chat().uuid();
+
+    harness.press(Key::ArrowDown, Modifiers::ALT);
+    assert_eq!(*harness.active_chat().uuid(), first);
+    harness.press(Key::ArrowUp, Modifiers::ALT);
+    assert_eq!(*harness.active_chat().uuid(), second);
+}
+
+#[test]
+fn question_mark_is_typed_in_the_message_not_a_shortcut() {
+    let mut harness = with_chat();
+
+    harness.press(Key::Character("?".to_string()), Modifiers::SHIFT);
+
+    assert!(!harness.html().contains("Change them in Settings"));
+}
+
+#[test]
+fn rebinding_reports_conflicts() {
+    let mut keymap = Keymap::default();
+    keymap.bind(Action::Rename, Shortcut::from_str("Alt+n").unwrap());
+    assert_eq!(keymap.conflicts(Action::Rename), vec![Action::NewChat]);
+}
+
+/// The bindings `prevent_default_js` hands the page, with the fields its keydown listener checks
+fn prevented(keymap: &Keymap) -> Vec<serde_json::Value> {
+    let js = keymap.prevent_default_js();
+    let start = js.find("window.letMeTalkShortcuts = ").unwrap() + "window.letMeTalkShortcuts = ".len();
+    let end = start + js[start..].find(";\n").unwrap();
+    serde_json::from_str(&js[start..end]).unwrap()
+}
+
+/// Whether the listener would stop a key pressed in the message box
+fn stops_in_message_box(shortcuts: &[serde_json::Value], key: &str, shift: bool) -> bool {
+    shortcuts.iter().any(|s| {
+        s["key"] == key
+            && s["ctrl"] == false
+            && s["alt"] == false
+            && s["meta"] == false
+            && (s["character"] == true || s["shift"] == shift)
+            && s["types"] == false
+    })
+}
+
+#[test]
+fn the_browser_leaves_send_and_newline_to_the_message_box() {
+    let shortcuts = prevented(&Keymap::default());
+    assert!(stops_in_message_box(&shortcuts, "enter", false));
+    assert!(stops_in_message_box(&shortcuts, "enter", true));
+    let send = shortcuts.iter().find(|s| s["key"] == "enter" && s["shift"] == false).unwrap();
+    assert_eq!(send["messageInput"], true);
+
+    // Swapped around, plain Enter still never reaches the textarea as a line break
+    let mut keymap = Keymap::default();
+    keymap.bind(Action::Send, Shortcut::from_str("Ctrl+Enter").unwrap());
+    keymap.bind(Action::Newline, Shortcut::key("Enter"));
+    let shortcuts = prevented(&keymap);
+    assert!(stops_in_message_box(&shortcuts, "enter", false));
+    assert!(shortcuts.iter().any(|s| s["key"] == "enter" && s["ctrl"] == true && s["messageInput"] == true));
+}
@@ -0,0 +1,104 @@
+mod harness;
+
+use dioxus::html::input_data::keyboard_types::{Key, Modifiers};
+use harness::Harness;
+use let_me_talk::colours::Rgb;
+use let_me_talk::data::{parse_duration, parse_slash, MessageKind, SlashRegistry};
+use std::time::Duration;
+
+#[test]
+fn slash_commands_parse_into_a_name_and_arguments() {
+    assert_eq!(parse_slash("/rename Morning pages"), Some(("rename", "Morning pages")));
+    assert_eq!(parse_slash("/undo"), Some(("undo", "")));
+    assert_eq!(parse_slash("//not a command"), None);
+    assert_eq!(parse_slash("/ nothing"), None);
+    assert_eq!(parse_slash("hello /rename"), None);
+}
+
+#[test]
+fn durations_accept_units_and_bare_minutes() {
+    assert_eq!(parse_duration("10m"), Some(Duration::from_secs(600)));
+    assert_eq!(parse_duration("1h30m"), Some(Duration::from_secs(5400)));
+    assert_eq!(parse_duration("90s"), Some(Duration::from_secs(90)));
+    assert_eq!(parse_duration("5"), Some(Duration::from_secs(300)));
+    assert_eq!(parse_duration("10x"), None);
+    assert_eq!(parse_duration("m"), None);
+}
+
+#[test]
+fn completions_rank_commands_by_their_name() {
+    let registry = SlashRegistry::builtin();
+    assert_eq!(registry.completions("ren")[0].name, "rename");
+    assert_eq!(registry.completions("")[0].name, "rename");
+    assert!(registry.get("UNDO").is_some());
+}
+
+#[test]
+fn commands_act_on_the_chat_instead_of_being_sent() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    let chat = harness.active_chat();
+
+    harness.type_text("/rename Morning pages");
+    harness.press(Key::Enter, Modifiers::empty());
+    assert_eq!(*chat.name.read(), "Morning pages");
+    assert!(chat.messages.read().msgs.is_empty());
+    assert!(chat.current_message.read().is_empty());
+
+    harness.type_text("first");
+    harness.press(Key::Enter, Modifiers::empty());
+    harness.type_text("/undo");
+    harness.press(Key::Enter, Modifiers::empty());
+    assert!(chat.messages.read().msgs.is_empty());
+    assert!(harness.html().contains("Removed"));
+}
+
+#[test]
+fn new_personas_and_narration_come_from_commands() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    let chat = harness.active_chat();
+
+    harness.type_text("/persona new Inner Critic #aa2233");
+    harness.press(Key::Enter, Modifiers::empty());
+    let critic = *chat.active_persona.read();
+    let personas = harness.state().personas;
+    assert_eq!(personas.read().get(&critic).unwrap().name, "Inner Critic");
+    assert_eq!(personas.read().get(&critic).unwrap().colour, Rgb(0xaa, 0x22, 0x33));
+
+    harness.type_text("/note A pause");
+    harness.press(Key::Enter, Modifiers::empty());
+    let messages = chat.messages.read();
+    assert_eq!(messages.msgs[0].msg, "A pause");
+    assert_eq!(messages.msgs[0].kind, MessageKind::Narration);
+    // Speaking carries on as the critic
+    assert_eq!(*chat.active_persona.read(), critic);
+}
+
+#[test]
+fn persona_needs_new_as_a_word_of_its_own() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    let count = harness.state().personas.read().count();
+
+    harness.type_text("/persona newton");
+    harness.press(Key::Enter, Modifiers::empty());
+    assert_eq!(harness.state().personas.read().count(), count);
+    assert_eq!(*harness.active_chat().current_message.read(), "/persona newton");
+}
+
+#[test]
+fn unknown_commands_are_kept_with_an_error_and_double_slashes_are_sent() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    let chat = harness.active_chat();
+
+    harness.type_text("/shrug");
+    harness.press(Key::Enter, Modifiers::empty());
+    assert_eq!(*chat.current_message.read(), "/shrug");
+    assert!(harness.html().contains("There's no /shrug"));
+
+    harness.type_text("//shrug");
+    harness.press(Key::Enter, Modifiers::empty());
+    assert_eq!(chat.messages.read().msgs[0].msg, "/shrug");
+}
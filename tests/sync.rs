@@ -0,0 +1,125 @@
+mod harness;
+
+use harness::Harness;
+use let_me_talk::colours::Rgb;
+use let_me_talk::data::{merge_messages, ChatData, MergeOrder, Message, Messages, Persona, Personas};
+use let_me_talk::sync::SyncDoc;
+use uuid::Uuid;
+
+fn message(persona: Uuid, msg: &str) -> Message {
+    serde_json::from_value(serde_json::json!({ "uuid": Uuid::new_v4(), "msg": msg, "persona": persona })).unwrap()
+}
+
+fn sent_at(persona: Uuid, msg: &str, sent: i64) -> Message {
+    Message { sent, ..message(persona, msg) }
+}
+
+fn chat(persona: Uuid, name: &str, msgs: &[&str]) -> ChatData {
+    ChatData {
+        uuid: Uuid::new_v4(),
+        name: name.to_string(),
+        messages: Messages { msgs: msgs.iter().map(|msg| message(persona, msg)).collect() },
+        active_persona: persona,
+        added_personas: [persona].into_iter().collect(),
+        ..Default::default()
+    }
+}
+
+fn texts(chat: &ChatData) -> Vec<&str> {
+    chat.messages.msgs.iter().map(|msg| msg.msg.as_str()).collect()
+}
+
+/// Two replicas that both start from a synced chat to keep and a chat to delete
+fn diverged() -> (SyncDoc, SyncDoc, Personas, Vec<ChatData>) {
+    let personas = Personas::new(Persona { name: "Me".to_string(), colour: Rgb(0x49, 0x55, 0x65) });
+    let me = *personas.0.keys().next().unwrap();
+    let kept = chat(me, "Base", &["one", "two"]);
+    let doomed = chat(me, "Doomed", &[]);
+
+    let mut a = SyncDoc::new();
+    a.observe(&personas, &[kept, doomed]);
+    let mut b = SyncDoc::new();
+    b.merge(&a);
+    let base = b.chats();
+
+    // A renames, adds a message and deletes "one" and the second chat
+    let mut ours = base[0].clone();
+    ours.name = "Ours".to_string();
+    ours.messages.msgs.push(message(me, "from a"));
+    ours.messages.msgs.remove(0);
+    a.observe(&personas, &[ours]);
+
+    // B renames and adds a message to both chats
+    let mut theirs = base;
+    theirs[0].name = "Theirs".to_string();
+    theirs[0].messages.msgs.push(message(me, "from b"));
+    theirs[1].messages.msgs.push(message(me, "too late"));
+    b.observe(&personas, &theirs);
+
+    (a, b, personas, theirs)
+}
+
+#[test]
+fn concurrent_edits_converge_whichever_way_they_merge() {
+    let (mut a, mut b, _, _) = diverged();
+    let a_before = a.clone();
+    a.merge(&b);
+    b.merge(&a_before);
+
+    assert_eq!(a.chats(), b.chats());
+    assert_eq!(a.personas(), b.personas());
+    let chats = a.chats();
+    assert_eq!(chats.len(), 1);
+    assert!(chats[0].name == "Ours" || chats[0].name == "Theirs");
+    let mut texts = texts(&chats[0]);
+    texts.sort();
+    assert_eq!(texts, ["from a", "from b", "two"]);
+}
+
+#[test]
+fn merging_twice_changes_nothing() {
+    let (mut a, b, _, _) = diverged();
+    a.merge(&b);
+    let once = a.clone();
+    a.merge(&b);
+    assert_eq!(a, once);
+    a.merge(&once);
+    assert_eq!(a, once);
+}
+
+#[test]
+fn a_deleted_message_never_comes_back() {
+    let (mut a, mut b, personas, theirs) = diverged();
+    b.merge(&a);
+    // B still has "one" locally until it reloads, seeing that again mustn't revive it
+    b.observe(&personas, &theirs[..1]);
+    a.merge(&b);
+    for doc in [&a, &b] {
+        let chats = doc.chats();
+        assert!(!texts(&chats[0]).contains(&"one"));
+    }
+}
+
+#[test]
+fn an_interleaved_merge_keeps_its_order_through_a_sync() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    let chat = harness.active_chat();
+    let me = *chat.active_persona.read();
+    let ours = vec![sent_at(me, "a1", 10), sent_at(me, "a2", 30)];
+    let theirs = vec![sent_at(me, "b1", 5), sent_at(me, "b2", 20)];
+    let state = harness.state();
+
+    // Our messages are seen first, so theirs come later to the sync
+    chat.messages.write().msgs = ours.clone();
+    let mut a = SyncDoc::new();
+    a.observe(&state.personas.read(), &state.chats.read().snapshot());
+    chat.messages.write().msgs = merge_messages(ours, theirs, MergeOrder::Interleave);
+    a.observe(&state.personas.read(), &state.chats.read().snapshot());
+
+    let mut b = SyncDoc::new();
+    b.merge(&a);
+    state.chats.write().apply_synced(b.chats());
+
+    assert_eq!(texts(&ChatData::from(&chat)), ["b1", "a1", "b2", "a2"]);
+}
@@ -0,0 +1,54 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use let_me_talk::sync::server::{Access, FileStore, KEPT_BLOBS};
+use uuid::Uuid;
+
+fn store() -> FileStore {
+    FileStore::new(std::env::temp_dir().join(format!("let-me-talk-sync-{}", Uuid::new_v4()))).unwrap()
+}
+
+#[test]
+fn only_an_explicit_claim_takes_a_user() {
+    let store = store();
+    assert_eq!(store.authorize("sam", "secret", false).unwrap(), Access::Unclaimed);
+    // Reading doesn't claim, so the next one to ask still can
+    assert_eq!(store.authorize("sam", "guess", false).unwrap(), Access::Unclaimed);
+
+    assert_eq!(store.authorize("sam", "secret", true).unwrap(), Access::Granted);
+    assert_eq!(store.authorize("sam", "secret", false).unwrap(), Access::Granted);
+    assert_eq!(store.authorize("sam", "guess", true).unwrap(), Access::Denied);
+    assert_eq!(store.authorize("sam", "guess", false).unwrap(), Access::Denied);
+}
+
+#[test]
+fn pushed_blobs_are_pulled_back_after_a_revision() {
+    let store = store();
+    store.authorize("sam", "secret", true).unwrap();
+    assert_eq!(store.push("sam", b"first").unwrap(), 1);
+    assert_eq!(store.push("sam", b"second").unwrap(), 2);
+
+    let all = store.since("sam", 0).unwrap();
+    assert_eq!(all.revision, 2);
+    let data: Vec<Vec<u8>> = all.blobs.iter().map(|blob| BASE64.decode(&blob.data).unwrap()).collect();
+    assert_eq!(data, [b"first".to_vec(), b"second".to_vec()]);
+
+    let newer = store.since("sam", 1).unwrap();
+    assert_eq!(newer.blobs.len(), 1);
+    assert_eq!(newer.blobs[0].revision, 2);
+    assert!(store.since("sam", 2).unwrap().blobs.is_empty());
+}
+
+#[test]
+fn only_the_latest_blobs_are_kept() {
+    let store = store();
+    store.authorize("sam", "secret", true).unwrap();
+    let pushes = KEPT_BLOBS as u64 + 3;
+    for i in 0..pushes {
+        store.push("sam", format!("blob {i}").as_bytes()).unwrap();
+    }
+    let blobs = store.since("sam", 0).unwrap();
+    assert_eq!(blobs.revision, pushes);
+    assert_eq!(blobs.blobs.len(), KEPT_BLOBS);
+    assert_eq!(blobs.blobs[0].revision, 4);
+    // Revisions carry on from the newest, never reusing a pruned one
+    assert_eq!(store.push("sam", b"next").unwrap(), pushes + 1);
+}
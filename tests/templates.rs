@@ -0,0 +1,75 @@
+mod harness;
+
+use harness::Harness;
+use let_me_talk::colours::Rgb;
+use let_me_talk::data::{ChatTemplate, MessageKind, Persona, Personas};
+use uuid::Uuid;
+
+fn morning_check_in(personas: &mut Personas) -> ChatTemplate {
+    let me = *personas.get_index(0).unwrap().0;
+    let critic = personas.push(Persona {
+        name: "Inner Critic".to_string(),
+        colour: Rgb(0xb0, 0x30, 0x30),
+    });
+    let planner = personas.push(Persona {
+        name: "Planner".to_string(),
+        colour: Rgb(0x30, 0x70, 0xb0),
+    });
+    ChatTemplate {
+        personas: [me, critic, planner].into_iter().collect(),
+        starting_persona: Some(planner),
+        opening: "How is everyone this morning?".to_string(),
+        name_format: "Check-in %Y".to_string(),
+        ..ChatTemplate::new("Morning check-in".to_string())
+    }
+}
+
+#[test]
+fn a_template_starts_the_chat_it_describes() {
+    let mut personas = Personas::starter();
+    let template = morning_check_in(&mut personas);
+    let chat = template.new_chat(&personas);
+
+    let expected: Vec<Uuid> = template.personas.iter().copied().collect();
+    assert_eq!(chat.added_personas.iter().copied().collect::<Vec<Uuid>>(), expected);
+    assert_eq!(Some(chat.active_persona), template.starting_persona);
+    assert_eq!(chat.name, format!("Check-in {}", chrono::Local::now().format("%Y")));
+    assert_eq!(chat.messages.msgs.len(), 1);
+    assert_eq!(chat.messages.msgs[0].kind, MessageKind::Narration);
+    assert_eq!(chat.messages.msgs[0].msg, "How is everyone this morning?");
+}
+
+#[test]
+fn deleted_personas_and_bad_formats_are_worked_around() {
+    let personas = Personas::starter();
+    let gone = Uuid::new_v4();
+    let template = ChatTemplate {
+        personas: [gone].into_iter().collect(),
+        starting_persona: Some(gone),
+        name_format: "%Q".to_string(),
+        ..ChatTemplate::new("Evening".to_string())
+    };
+    let chat = template.new_chat(&personas);
+    let first = *personas.get_index(0).unwrap().0;
+    assert_eq!(chat.active_persona, first);
+    assert_eq!(chat.added_personas.len(), 1);
+    assert!(chat.name.starts_with("Evening, "));
+    assert!(chat.messages.msgs.is_empty());
+    // The template's own name can have a % in it without breaking the default format
+    assert!(ChatTemplate::new("100% me".to_string()).chat_name().starts_with("100% me, "));
+}
+
+#[test]
+fn new_chat_offers_the_templates() {
+    let mut harness = Harness::new();
+    let state = harness.state();
+    let template = morning_check_in(&mut state.personas.write());
+    state.settings.write().templates.push(template.clone());
+
+    harness.click_text("New Chat");
+    harness.click_text("Morning check-in");
+    let chat = harness.active_chat();
+    assert_eq!(*chat.added_personas.read(), template.personas);
+    assert_eq!(Some(*chat.active_persona.read()), template.starting_persona);
+    assert!(harness.html().contains("How is everyone this morning?"));
+}
@@ -0,0 +1,65 @@
+mod harness;
+
+use dioxus::html::input_data::keyboard_types::{Key, Modifiers};
+use harness::Harness;
+use let_me_talk::data::{ChatData, JournalExport, MessageKind, Personas};
+use uuid::Uuid;
+
+/// A chat where `b` and `d` answer `a`, `c` answers `b` and `e` stands alone
+fn branching_chat() -> (ChatData, [Uuid; 5]) {
+    let me = Uuid::new_v4();
+    let mut chat = ChatData::new(me);
+    for text in ["a", "b", "c", "d", "e"] {
+        chat.send(me, text.to_string());
+    }
+    let ids: Vec<Uuid> = chat.messages.msgs.iter().map(|msg| msg.uuid).collect();
+    chat.messages.msgs[1].reply_to = Some(ids[0]);
+    chat.messages.msgs[2].reply_to = Some(ids[1]);
+    chat.messages.msgs[3].reply_to = Some(ids[0]);
+    (chat, [ids[0], ids[1], ids[2], ids[3], ids[4]])
+}
+
+#[test]
+fn a_thread_follows_replies_back_to_the_start_and_on_to_every_answer() {
+    let (chat, [a, b, c, d, _]) = branching_chat();
+    let thread: Vec<Uuid> = chat.messages.thread(&b).into_iter().collect();
+    assert_eq!(thread, [a, b, c]);
+    let thread: Vec<Uuid> = chat.messages.thread(&a).into_iter().collect();
+    assert_eq!(thread, [a, b, c, d]);
+    assert_eq!(chat.messages.reply_count(&a), 2);
+}
+
+#[test]
+fn replies_survive_a_json_round_trip_and_are_quoted_in_markdown() {
+    let (chat, [a, b, ..]) = branching_chat();
+    let export = JournalExport {
+        personas: Personas::starter(),
+        chats: vec![chat],
+    };
+    let json = serde_json::to_string(&export).unwrap();
+    let imported: JournalExport = serde_json::from_str(&json).unwrap();
+    let chat = &imported.chats[0];
+    assert_eq!(chat.messages.get(&b).unwrap().reply_to, Some(a));
+    assert!(chat.to_markdown(&imported.personas).contains("> ↪ Unknown: a\n\n**Unknown**: b"));
+}
+
+#[test]
+fn replying_links_the_next_message_to_the_chosen_one() {
+    let mut harness = Harness::new();
+    harness.click_text("New Chat");
+    let chat = harness.active_chat();
+    harness.type_text("You always do this");
+    harness.press(Key::Enter, Modifiers::empty());
+
+    harness.click_text("Reply");
+    assert!(harness.html().contains("Replying to"));
+    harness.type_text("Not always");
+    harness.press(Key::Enter, Modifiers::empty());
+
+    let messages = chat.messages.read();
+    assert_eq!(messages.msgs[1].reply_to, Some(messages.msgs[0].uuid));
+    assert_eq!(messages.msgs[1].kind, MessageKind::Speech);
+    let html = harness.html();
+    assert!(html.contains("↪"));
+    assert!(!html.contains("Replying to"));
+}